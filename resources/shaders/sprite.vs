@@ -1,12 +1,14 @@
 #version 410 core
 layout (location = 0) in vec4 vertex;  // <vec2 position, vec2 texCoords>
+layout (location = 1) in vec4 color;
 
 out vec2 TexCoords;
+out vec4 SpriteColor;
 
-uniform mat4 model;
 uniform mat4 projection;
 
 void main() {
-    gl_Position = projection * model * vec4(vertex.xy, 0.0, 1.0);
+    gl_Position = projection * vec4(vertex.xy, 0.0, 1.0);
     TexCoords = vertex.zw;
+    SpriteColor = color;
 }
\ No newline at end of file
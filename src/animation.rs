@@ -0,0 +1,147 @@
+use image::RgbaImage;
+use nalgebra_glm as glm;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlayMode {
+    Loop,
+    PingPong,
+    Once,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    /// Part of the sprite's texture to show, as in [`crate::sprite_renderer::SpriteDesc`].
+    pub uv_rect: [f32; 4],
+    pub duration: f32,
+    /// Multiplied with the object's own color and alpha.
+    pub tint: glm::TVec4<f32>,
+}
+
+impl Frame {
+    pub fn new(uv_rect: [f32; 4], duration: f32) -> Self {
+        Self {
+            uv_rect,
+            duration,
+            tint: glm::vec4(1.0, 1.0, 1.0, 1.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Animation {
+    frames: Vec<Frame>,
+    mode: PlayMode,
+    current: usize,
+    elapsed: f32,
+    // +1 or -1, only ever negative while ping-ponging back
+    step: isize,
+    finished: bool,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<Frame>, mode: PlayMode) -> Self {
+        assert!(!frames.is_empty(), "An animation needs at least one frame");
+        Self {
+            frames,
+            mode,
+            current: 0,
+            elapsed: 0.0,
+            step: 1,
+            finished: false,
+        }
+    }
+
+    /// Frames laid out left to right in a single row, looping by default.
+    pub fn from_strip(frame_count: usize, frame_duration: f32) -> Self {
+        Self::from_grid(frame_count, 1, frame_count, frame_duration)
+    }
+
+    /// Frames read row by row from a sheet of `columns` x `rows` equally sized cells, looping by
+    /// default.
+    pub fn from_grid(columns: usize, rows: usize, frame_count: usize, frame_duration: f32) -> Self {
+        let (cell_width, cell_height) = (1.0 / columns as f32, 1.0 / rows as f32);
+        let frames = (0..frame_count.min(columns * rows))
+            .map(|i| {
+                let (column, row) = ((i % columns) as f32, (i / columns) as f32);
+                Frame::new(
+                    [
+                        column * cell_width,
+                        row * cell_height,
+                        (column + 1.0) * cell_width,
+                        (row + 1.0) * cell_height,
+                    ],
+                    frame_duration,
+                )
+            })
+            .collect();
+        Self::new(frames, PlayMode::Loop)
+    }
+
+    pub fn with_mode(mut self, mode: PlayMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.finished {
+            return;
+        }
+
+        self.elapsed += dt;
+        // zero-length frames would never let the loop finish
+        while self.elapsed >= self.frames[self.current].duration.max(f32::EPSILON) {
+            self.elapsed -= self.frames[self.current].duration.max(f32::EPSILON);
+
+            let last = self.frames.len() - 1;
+            match self.mode {
+                PlayMode::Loop => self.current = (self.current + 1) % self.frames.len(),
+                PlayMode::Once if self.current == last => {
+                    self.finished = true;
+                    return;
+                }
+                PlayMode::Once => self.current += 1,
+                PlayMode::PingPong if last == 0 => {}
+                PlayMode::PingPong => {
+                    if (self.step > 0 && self.current == last)
+                        || (self.step < 0 && self.current == 0)
+                    {
+                        self.step = -self.step;
+                    }
+                    self.current = self.current.saturating_add_signed(self.step);
+                }
+            }
+        }
+    }
+
+    pub fn frame(&self) -> &Frame {
+        &self.frames[self.current]
+    }
+
+    /// Only ever true for [`PlayMode::Once`], after the last frame has been shown for its full
+    /// duration.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+/// Builds a horizontal strip of `frames` cells from `image`, each cell knocking out more blocks of
+/// pixels than the last, so a brick appears to crumble when played through.
+pub fn crumble_strip(image: &RgbaImage, frames: u32) -> RgbaImage {
+    const BLOCK: u32 = 16;
+    let (width, height) = image.dimensions();
+    let mut strip = RgbaImage::new(width * frames, height);
+
+    for frame in 0..frames {
+        let threshold = frame as f32 / frames as f32;
+        for (x, y, pixel) in image.enumerate_pixels() {
+            // cheap integer hash so every block gets a fixed crumble order
+            let block = (x / BLOCK).wrapping_mul(73_856_093) ^ (y / BLOCK).wrapping_mul(19_349_663);
+            let order = (block.wrapping_mul(2_654_435_761) >> 24) as f32 / 255.0;
+            if order >= threshold {
+                strip.put_pixel(frame * width + x, y, *pixel);
+            }
+        }
+    }
+
+    strip
+}
@@ -3,36 +3,67 @@ use std::rc::Rc;
 use nalgebra_glm as glm;
 
 use crate::{
+    animation::{
+        Animation,
+        Frame,
+        PlayMode,
+    },
     game_object::GameObject,
     sprite_renderer::SpriteRenderer,
     texture::Texture2D,
 };
 
+// how quickly spin wears off, per second
+const SPIN_DECAY: f32 = 1.5;
+// degrees per second the flight path bends for each unit of spin when curving is enabled
+const CURVE_PER_SPIN: f32 = 5.0;
+
+#[derive(Clone)]
 pub struct BallObject {
     pub game_obj: GameObject,
     pub radius: f32,
     pub stuck: bool,
+    /// Picked up from a moving paddle, positive for clockwise; decays over time.
+    pub spin: f32,
 }
 
 impl BallObject {
     pub fn new(position: glm::TVec2<f32>, sprite: Rc<Texture2D>, radius: f32) -> Self {
+        let mut game_obj = GameObject::new(
+            position,
+            glm::vec2(radius * 2.0, radius * 2.0),
+            glm::vec2(0.0, 0.0),
+            sprite,
+            glm::vec3(1.0, 1.0, 1.0),
+        );
+        game_obj.animation = Some(stuck_pulse());
+
         Self {
-            game_obj: GameObject::new(
-                position,
-                glm::vec2(radius * 2.0, radius * 2.0),
-                glm::vec2(0.0, 0.0),
-                sprite,
-                glm::vec3(1.0, 1.0, 1.0),
-            ),
+            game_obj,
             radius,
             stuck: true,
+            spin: 0.0,
         }
     }
 
-    pub fn move_ball(&mut self, dt: f32, window_width: u32) {
+    /// Advances the ball by `dt`; with `curve` its spin also bends the flight path. Without a
+    /// `ceiling` the ball carries on out of the top of the window. Returns whether the ball
+    /// bounced off the ceiling.
+    pub fn move_ball(&mut self, dt: f32, window_width: u32, curve: bool, ceiling: bool) -> bool {
+        let mut hit_ceiling = false;
         if !self.stuck {
+            if curve && self.spin != 0.0 {
+                // turn the velocity without changing the speed
+                let angle = (self.spin * CURVE_PER_SPIN * dt).to_radians();
+                self.game_obj.velocity = glm::rotate_vec2(&self.game_obj.velocity, angle);
+            }
+            self.spin *= (-SPIN_DECAY * dt).exp();
+
             // move the ball
             self.game_obj.position += self.game_obj.velocity * dt;
+            // roll along with the horizontal motion, plus whatever spin it carries
+            self.game_obj.rotation += (self.game_obj.velocity.x / self.radius).to_degrees() * dt
+                + self.spin.to_degrees() * dt;
             // then check if outside window bounds and if so, reverse velocity and restore at
             // correct position
             if self.game_obj.position.x <= 0.0 {
@@ -42,22 +73,50 @@ impl BallObject {
                 self.game_obj.velocity.x = -self.game_obj.velocity.x;
                 self.game_obj.position.x = window_width as f32 - self.game_obj.size.x;
             }
-            if self.game_obj.position.y <= 0.0 {
+            if ceiling && self.game_obj.position.y <= 0.0 {
                 self.game_obj.velocity.y = -self.game_obj.velocity.y;
                 self.game_obj.position.y = 0.0;
+                hit_ceiling = true;
             }
         }
+        hit_ceiling
     }
 
     pub fn reset(&mut self, position: glm::TVec2<f32>, velocity: glm::TVec2<f32>) {
         self.game_obj.position = position;
         self.game_obj.velocity = velocity;
+        self.game_obj.rotation = 0.0;
+        self.game_obj.animation = Some(stuck_pulse());
         self.stuck = true;
+        self.spin = 0.0;
         //self.sticky = false;
         //self.pass_through = false;
     }
 
+    /// Holds the ball where it is, keeping its velocity for when it is launched again.
+    pub fn catch(&mut self) {
+        self.stuck = true;
+        self.spin = 0.0;
+    }
+
+    pub fn launch(&mut self, velocity: glm::TVec2<f32>) {
+        self.stuck = false;
+        self.game_obj.velocity = velocity;
+        self.game_obj.animation = None;
+    }
+
     pub fn draw(&self, renderer: &SpriteRenderer) {
         self.game_obj.draw(renderer);
     }
 }
+
+// gently pulses the ball while it waits on the paddle to be launched
+fn stuck_pulse() -> Animation {
+    let frames = [1.0, 0.85, 0.7]
+        .map(|brightness| Frame {
+            tint: glm::vec4(brightness, brightness, brightness, 1.0),
+            ..Frame::new([0.0, 0.0, 1.0, 1.0], 0.15)
+        })
+        .to_vec();
+    Animation::new(frames, PlayMode::PingPong)
+}
@@ -53,7 +53,7 @@ fn load_texture(gl: &glow::Context, path: &str) -> Result<glow::Texture, Box<dyn
 fn main() {
     unsafe {
         // Create a context from a glutin window on non-wasm32 targets
-        let (gl, gl_surface, gl_context, shader_version, window, event_loop) = {
+        let (gl, gl_surface, gl_context, _shader_version, window, event_loop) = {
             use std::num::NonZeroU32;
 
             use glutin::{
@@ -261,9 +261,6 @@ fn main() {
         gl.bind_buffer(glow::ARRAY_BUFFER, None);
         gl.bind_vertex_array(None);
 
-        let mut current_width = SCR_WIDTH;
-        let mut current_height = SCR_HEIGHT;
-
         // Get uniform locations
         let model_loc = gl.get_uniform_location(program, "model");
         let projection_loc = gl.get_uniform_location(program, "projection");
@@ -338,8 +335,8 @@ fn main() {
                             gl_surface.swap_buffers(&gl_context).unwrap();
                         }
                         WindowEvent::Resized(physical_size) => {
-                            current_width = physical_size.width;
-                            current_height = physical_size.height;
+                            let current_width = physical_size.width;
+                            let current_height = physical_size.height;
 
                             // Update OpenGL viewport
                             gl.viewport(0, 0, current_width as i32, current_height as i32);
@@ -354,18 +351,13 @@ fn main() {
                             window.request_redraw();
                         }
                         WindowEvent::KeyboardInput {
-                            event:
-                                KeyEvent {
-                                    physical_key,
-                                    state,
-                                    ..
-                                },
+                            event: KeyEvent { .. },
                             ..
                         } => {}
-                        WindowEvent::MouseInput { state, button, .. } => {}
+                        WindowEvent::MouseInput { .. } => {}
 
                         // Add mouse wheel handling for zoom
-                        WindowEvent::MouseWheel { delta, .. } => {}
+                        WindowEvent::MouseWheel { .. } => {}
 
                         _ => (),
                     }
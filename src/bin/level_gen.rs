@@ -0,0 +1,81 @@
+//! Writes procedurally generated levels in the `.lvl` format the game reads.
+//!
+//! ```text
+//! cargo run --bin level_gen -- [--seed N] [--columns N] [--rows N] [--density 0.0-1.0]
+//!     [--solid 0.0-1.0] [--symmetry none|mirror] [--bands N|random] [--out path.lvl]
+//! ```
+//!
+//! Without `--out` the level is printed instead.
+
+use std::str::FromStr;
+
+use breakout_rs::{
+    level_generator::{
+        GeneratorConfig,
+        generate,
+        to_level_file,
+    },
+    rng::Rng,
+};
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let mut config = GeneratorConfig::default();
+    let mut seed = Rng::seed_from_time();
+    let mut out = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| exit_with(&format!("{} needs a value", arg)))
+                .as_str()
+        };
+        match arg.as_str() {
+            "--seed" => seed = parse(arg, value()),
+            "--columns" => config.columns = parse(arg, value()),
+            "--rows" => config.rows = parse(arg, value()),
+            "--density" => config.density = parse::<f32>(arg, value()).clamp(0.0, 1.0),
+            "--solid" => config.solid_ratio = parse::<f32>(arg, value()).clamp(0.0, 1.0),
+            "--symmetry" => config.symmetry = parse(arg, value()),
+            "--bands" => config.coloring = parse(arg, value()),
+            "--out" => out = Some(value().to_string()),
+            _ => exit_with(&format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    let tiles = generate(&config, seed);
+    let comment = format!(
+        "generated with seed {}\n\
+         columns {}, rows {}, density {}, solid {}, symmetry {}, bands {}",
+        seed,
+        config.columns,
+        config.rows,
+        config.density,
+        config.solid_ratio,
+        config.symmetry,
+        config.coloring
+    );
+    let contents = to_level_file(&tiles, &comment);
+
+    match out {
+        Some(path) => {
+            if let Err(err) = std::fs::write(&path, contents) {
+                exit_with(&format!("Failed to write {}: {}", path, err));
+            }
+            println!("Wrote {} (seed {})", path, seed);
+        }
+        None => print!("{}", contents),
+    }
+}
+
+fn parse<T: FromStr>(arg: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| exit_with(&format!("invalid value '{}' for {}", value, arg)))
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("level_gen: {}", message);
+    std::process::exit(1);
+}
@@ -0,0 +1,288 @@
+//! Plays two sides of a network game against each other in one process, over a connection made
+//! as bad as asked, and checks that both sides ended up playing the same game.
+//!
+//! ```text
+//! cargo run --bin net_check -- [--ticks N] [--lag ms] [--jitter ms] [--loss 0.0-1.0]
+//!     [--udp port]
+//! ```
+//!
+//! The two sides talk through an in-memory loopback, or with `--udp` through real sockets on
+//! localhost. Either way they step a small stand-in for the game, two paddles and a ball, with
+//! inputs made up as they go.
+
+use std::{
+    str::FromStr,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use breakout_rs::{
+    netplay::{
+        self,
+        Loopback,
+        NetSession,
+        NetStatus,
+        SimulatedLag,
+        TICK,
+        TickInput,
+        Transport,
+        UdpTransport,
+    },
+    rng::Rng,
+};
+
+const PADDLE_WIDTH: f32 = 0.2;
+const PADDLE_SPEED: f32 = 0.8;
+const BALL_SPEED: f32 = 0.6;
+// give up on a run that stops making progress
+const RUN_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let mut ticks = 600;
+    let mut lag = 0.0;
+    let mut jitter = 0.0;
+    let mut loss = 0.0;
+    let mut udp_port = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| exit_with(&format!("{} needs a value", arg)))
+                .as_str()
+        };
+        match arg.as_str() {
+            "--ticks" => ticks = parse(arg, value()),
+            "--lag" => lag = parse(arg, value()),
+            "--jitter" => jitter = parse(arg, value()),
+            "--loss" => loss = parse::<f32>(arg, value()).clamp(0.0, 1.0),
+            "--udp" => udp_port = Some(parse::<u16>(arg, value())),
+            _ => exit_with(&format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    let (host, join): (Box<dyn Transport>, Box<dyn Transport>) = match udp_port {
+        Some(port) => {
+            let host = UdpTransport::host(port)
+                .unwrap_or_else(|err| exit_with(&format!("Failed to open port {}: {}", port, err)));
+            let join = UdpTransport::join(&format!("127.0.0.1:{}", port))
+                .unwrap_or_else(|err| exit_with(&format!("Failed to reach the host: {}", err)));
+            (Box::new(host), Box::new(join))
+        }
+        None => {
+            let (host, join) = Loopback::pair();
+            (Box::new(host), Box::new(join))
+        }
+    };
+    let worse = |transport| -> Box<dyn Transport> {
+        Box::new(SimulatedLag::new(
+            transport,
+            Duration::from_secs_f32(lag / 1000.0),
+            Duration::from_secs_f32(jitter / 1000.0),
+            loss,
+        ))
+    };
+
+    let seed = Rng::seed_from_time();
+    let mut sides = [
+        Side::new(NetSession::host(worse(host), seed, b"net_check".to_vec())),
+        Side::new(NetSession::join(worse(join))),
+    ];
+
+    let started = Instant::now();
+    let mut last_frame = started;
+    while sides.iter().any(|side| side.session.frame() < ticks) {
+        if started.elapsed() > RUN_TIMEOUT {
+            exit_with("timed out, the sides stopped making progress");
+        }
+        std::thread::sleep(Duration::from_secs_f32(TICK / 2.0));
+        let dt = last_frame.elapsed().as_secs_f32();
+        last_frame = Instant::now();
+        for side in sides.iter_mut() {
+            side.update(dt);
+        }
+    }
+    // let the last inputs arrive, so as much of the game as possible can be compared
+    let settle = Instant::now();
+    while settle.elapsed() < Duration::from_secs_f32(lag / 1000.0 * 2.0 + 0.5) {
+        std::thread::sleep(Duration::from_secs_f32(TICK / 2.0));
+        for side in sides.iter_mut() {
+            side.session.poll();
+            side.session
+                .update(&mut side.sim, 0.0, TickInput::default());
+        }
+    }
+
+    let [host, join] = &sides;
+    if join.session.setup() != host.session.setup() {
+        exit_with("the joining side didn't get the host's setup");
+    }
+    for side in &sides {
+        println!(
+            "player {}: {} ticks, {} still guessed, {} rollbacks",
+            side.session.local_player() + 1,
+            side.session.frame(),
+            side.session.prediction(),
+            side.session.rollbacks()
+        );
+    }
+    let confirmed = sides
+        .iter()
+        .map(|side| side.session.frame() - side.session.prediction())
+        .min()
+        .unwrap_or(0);
+    let mismatch =
+        (0..confirmed).find(|&tick| host.sim.checksums[tick] != join.sim.checksums[tick]);
+    match mismatch {
+        Some(tick) => exit_with(&format!("the sides went apart at tick {}", tick)),
+        None => println!("both sides played the same {} ticks", confirmed),
+    }
+}
+
+// one side of the game: its session, its copy of the game and the player making up inputs
+struct Side {
+    session: NetSession<Pong>,
+    sim: Pong,
+    rng: Rng,
+    input: TickInput,
+}
+
+impl Side {
+    fn new(session: NetSession<Pong>) -> Self {
+        let rng = Rng::new(Rng::seed_from_time() + session.local_player() as u64);
+        Self {
+            session,
+            sim: Pong::new(),
+            rng,
+            input: TickInput::default(),
+        }
+    }
+
+    fn update(&mut self, dt: f32) {
+        self.session.poll();
+        if self.session.status() == NetStatus::Disconnected {
+            exit_with("lost the connection");
+        }
+        // change direction now and then, and launch once in a while
+        if self.rng.chance(0.05) {
+            self.input = TickInput::new(self.rng.next_f32() * 2.0 - 1.0, false);
+        }
+        let input = TickInput::new(self.input.axis(), self.rng.below(50) == 0);
+        self.session.update(&mut self.sim, dt, input);
+    }
+}
+
+// a stand-in for the game, on a field one unit square: a paddle along the bottom and one along
+// the top, and a ball that waits on player one's paddle until launched
+#[derive(Clone)]
+struct PongState {
+    paddles: [f32; 2],
+    ball: (f32, f32),
+    velocity: (f32, f32),
+    launched: bool,
+    misses: [u32; 2],
+}
+
+struct Pong {
+    state: PongState,
+    // the state after each tick, by tick, to compare between the sides
+    checksums: Vec<u64>,
+}
+
+impl Pong {
+    fn new() -> Self {
+        Self {
+            state: PongState {
+                paddles: [0.5; 2],
+                ball: (0.5, 0.9),
+                velocity: (0.0, 0.0),
+                launched: false,
+                misses: [0; 2],
+            },
+            checksums: Vec::new(),
+        }
+    }
+
+    fn checksum(&self) -> u64 {
+        let state = &self.state;
+        let bits = [
+            state.paddles[0].to_bits(),
+            state.paddles[1].to_bits(),
+            state.ball.0.to_bits(),
+            state.ball.1.to_bits(),
+            state.velocity.0.to_bits(),
+            state.velocity.1.to_bits(),
+            state.launched as u32,
+            state.misses[0],
+            state.misses[1],
+        ];
+        bits.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &word| {
+            (hash ^ word as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+}
+
+impl netplay::Simulation for Pong {
+    type State = (PongState, usize);
+
+    fn save_state(&self) -> Self::State {
+        (self.state.clone(), self.checksums.len())
+    }
+
+    fn load_state(&mut self, state: &Self::State) {
+        self.state = state.0.clone();
+        self.checksums.truncate(state.1);
+    }
+
+    fn advance(&mut self, inputs: [TickInput; 2]) {
+        let state = &mut self.state;
+        for (paddle, input) in state.paddles.iter_mut().zip(inputs) {
+            *paddle = (*paddle + input.axis() * PADDLE_SPEED * TICK).clamp(0.0, 1.0);
+        }
+        if !state.launched {
+            state.ball = (state.paddles[0], 0.9);
+            if inputs[0].launch {
+                state.launched = true;
+                state.velocity = (BALL_SPEED * 0.6, -BALL_SPEED);
+            }
+        } else {
+            state.ball.0 += state.velocity.0 * TICK;
+            state.ball.1 += state.velocity.1 * TICK;
+            if !(0.0..=1.0).contains(&state.ball.0) {
+                state.velocity.0 = -state.velocity.0;
+                state.ball.0 = state.ball.0.clamp(0.0, 1.0);
+            }
+            // each paddle guards its own edge, player one the bottom and player two the top
+            for (player, edge, bounce) in [(0, 1.0, -1.0), (1, 0.0, 1.0)] {
+                let past = (state.ball.1 - edge) * bounce <= 0.0;
+                if !past {
+                    continue;
+                }
+                if (state.ball.0 - state.paddles[player]).abs() <= PADDLE_WIDTH / 2.0 {
+                    state.velocity.1 = BALL_SPEED * bounce;
+                    state.velocity.0 += (state.ball.0 - state.paddles[player]) * BALL_SPEED;
+                    state.ball.1 = edge;
+                } else {
+                    state.misses[player] += 1;
+                    state.launched = false;
+                }
+            }
+        }
+        let checksum = self.checksum();
+        self.checksums.push(checksum);
+    }
+}
+
+fn parse<T: FromStr>(arg: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| exit_with(&format!("invalid value '{}' for {}", value, arg)))
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("net_check: {}", message);
+    std::process::exit(1);
+}
@@ -0,0 +1,338 @@
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+
+use crate::{
+    animation::{
+        Animation,
+        Frame,
+        PlayMode,
+    },
+    brick_motion::parse_point,
+    game_object::GameObject,
+    projectile::{
+        Projectile,
+        ProjectileKind,
+    },
+    resource_manager::ResourceManager,
+    sprite_renderer::{
+        SpriteDesc,
+        SpriteRenderer,
+    },
+    texture::Texture2D,
+};
+
+// how far the boss sways either way, in tiles, and how long one sway takes at the base speed
+const SWAY_AMPLITUDE: f32 = 1.5;
+const SWAY_PERIOD: f32 = 6.0;
+const SHOT_SIZE: (f32, f32) = (8.0, 16.0);
+// score for every hit on a part, and for knocking one out
+const HIT_POINTS: u32 = 10;
+const PART_POINTS: u32 = 50;
+// the first attack of a phase comes this soon after it starts
+const PHASE_WARNING: f32 = 1.0;
+const HEALTH_BAR_HEIGHT: f32 = 4.0;
+
+/// How the boss fires at the paddle.
+#[derive(Clone, Copy, Debug)]
+pub enum Attack {
+    /// One shot at the paddle from each part in turn.
+    Aimed,
+    /// A fan of `shots` aimed at the paddle, `spread` degrees apart, from each part in turn.
+    Spread { shots: u32, spread: f32 },
+    /// One shot at the paddle from every part at once.
+    Volley,
+}
+
+/// A stage of the fight, entered once the boss is down to `health_share` of its health.
+struct Phase {
+    health_share: f32,
+    attack: Attack,
+    kind: ProjectileKind,
+    // seconds between attacks
+    interval: f32,
+    shot_speed: f32,
+    // multiplies how fast the boss sways
+    sway_speed: f32,
+}
+
+const PHASES: [Phase; 3] = [
+    Phase {
+        health_share: 1.0,
+        attack: Attack::Aimed,
+        kind: ProjectileKind::Shrink,
+        interval: 2.5,
+        shot_speed: 250.0,
+        sway_speed: 1.0,
+    },
+    Phase {
+        health_share: 2.0 / 3.0,
+        attack: Attack::Spread {
+            shots: 3,
+            spread: 15.0,
+        },
+        kind: ProjectileKind::Shrink,
+        interval: 2.2,
+        shot_speed: 280.0,
+        sway_speed: 1.5,
+    },
+    Phase {
+        health_share: 1.0 / 3.0,
+        attack: Attack::Volley,
+        kind: ProjectileKind::Lethal,
+        interval: 1.8,
+        shot_speed: 320.0,
+        sway_speed: 2.0,
+    },
+];
+
+/// One hit zone of the boss; it is knocked out once its health runs out.
+#[derive(Clone)]
+pub struct BossPart {
+    pub game_obj: GameObject,
+    pub health: u32,
+    pub max_health: u32,
+    base_color: glm::TVec3<f32>,
+}
+
+/// A large enemy made of several parts, each with its own health. It sways across the top of the
+/// level and fires at the paddle, harder as its total health drops through its phases.
+#[derive(Clone)]
+pub struct Boss {
+    pub parts: Vec<BossPart>,
+    phase: usize,
+    attack_timer: f32,
+    // the part whose turn it is to fire
+    next_part: usize,
+    sway_time: f32,
+    // in pixels, where the sway had taken the boss at the last update
+    sway_offset: f32,
+    unit_width: f32,
+    shot_sprite: Rc<Texture2D>,
+    bar_sprite: Rc<Texture2D>,
+}
+
+impl Boss {
+    pub fn new(unit_width: f32, resource_manager: &ResourceManager) -> Self {
+        Self {
+            parts: Vec::new(),
+            phase: 0,
+            attack_timer: PHASES[0].interval,
+            next_part: 0,
+            sway_time: 0.0,
+            sway_offset: 0.0,
+            unit_width,
+            shot_sprite: resource_manager.get_texture("particle"),
+            bar_sprite: resource_manager.get_texture("white"),
+        }
+    }
+
+    pub fn add_part(
+        &mut self,
+        position: glm::TVec2<f32>,
+        size: glm::TVec2<f32>,
+        health: u32,
+        resource_manager: &ResourceManager,
+    ) {
+        let base_color = glm::vec3(0.75, 0.3, 0.9);
+        let mut game_obj = GameObject::new(
+            position,
+            size,
+            glm::vec2(0.0, 0.0),
+            resource_manager.get_texture("block_solid"),
+            base_color,
+        );
+        game_obj.is_solid = true;
+        self.parts.push(BossPart {
+            game_obj,
+            health,
+            max_health: health,
+            base_color,
+        });
+    }
+
+    /// Sways the boss and plays its animations.
+    pub fn update(&mut self, dt: f32) {
+        for part in self.parts.iter_mut() {
+            part.game_obj.update_animation(dt);
+            part.game_obj.velocity = glm::vec2(0.0, 0.0);
+        }
+        if dt <= 0.0 {
+            return;
+        }
+        self.sway_time += dt * PHASES[self.phase].sway_speed;
+        let phase = self.sway_time / SWAY_PERIOD * std::f32::consts::TAU;
+        let offset = SWAY_AMPLITUDE * self.unit_width * phase.sin();
+        let moved = offset - self.sway_offset;
+        self.sway_offset = offset;
+        for part in self.parts.iter_mut() {
+            part.game_obj.position.x += moved;
+            part.game_obj.velocity.x = moved / dt;
+        }
+    }
+
+    /// Advances the attack clock, returning whatever the boss fires at `target` this update.
+    pub fn attack(&mut self, dt: f32, target: glm::TVec2<f32>) -> Vec<Projectile> {
+        let mut shots = Vec::new();
+        if self.is_defeated() {
+            return shots;
+        }
+        self.attack_timer -= dt;
+        if self.attack_timer > 0.0 {
+            return shots;
+        }
+        let phase = &PHASES[self.phase];
+        self.attack_timer += phase.interval;
+
+        let alive = (0..self.parts.len())
+            .filter(|&i| !self.parts[i].game_obj.destroyed)
+            .collect::<Vec<_>>();
+        let shooters = match phase.attack {
+            Attack::Aimed | Attack::Spread { .. } => {
+                self.next_part = (self.next_part + 1) % alive.len();
+                vec![alive[self.next_part]]
+            }
+            Attack::Volley => alive,
+        };
+        for i in shooters {
+            let obj = &self.parts[i].game_obj;
+            let muzzle = glm::vec2(
+                obj.position.x + obj.size.x / 2.0,
+                obj.position.y + obj.size.y,
+            );
+            let aim = target - muzzle;
+            let aim = if glm::length(&aim) > 0.0 {
+                glm::normalize(&aim)
+            } else {
+                glm::vec2(0.0, 1.0)
+            };
+            let angles = match phase.attack {
+                Attack::Spread { shots, spread } => (0..shots)
+                    .map(|shot| (shot as f32 - (shots - 1) as f32 / 2.0) * spread)
+                    .collect(),
+                Attack::Aimed | Attack::Volley => vec![0.0],
+            };
+            for angle in angles {
+                let direction = glm::rotate_vec2(&aim, f32::to_radians(angle));
+                shots.push(Projectile::new(
+                    muzzle,
+                    glm::vec2(SHOT_SIZE.0, SHOT_SIZE.1),
+                    direction * phase.shot_speed,
+                    phase.kind,
+                    self.shot_sprite.clone(),
+                ));
+            }
+        }
+        shots
+    }
+
+    /// Damages a part by one. Returns the points scored.
+    pub fn hit(&mut self, part: usize) -> u32 {
+        let part = &mut self.parts[part];
+        if part.game_obj.destroyed {
+            return 0;
+        }
+        part.health -= 1;
+        let share = part.health as f32 / part.max_health as f32;
+        // fade towards a dull red as the part wears down
+        part.game_obj.color = glm::lerp(&glm::vec3(0.5, 0.1, 0.1), &part.base_color, share);
+        part.game_obj.animation = Some(hit_flash());
+        let mut points = HIT_POINTS;
+        if part.health == 0 {
+            part.game_obj.destroyed = true;
+            points += PART_POINTS;
+        }
+
+        let phase = PHASES
+            .iter()
+            .rposition(|phase| self.health_share() <= phase.health_share)
+            .unwrap_or(0);
+        if phase > self.phase {
+            self.phase = phase;
+            self.attack_timer = PHASE_WARNING;
+            for part in self.parts.iter_mut() {
+                part.game_obj.animation = Some(hit_flash());
+            }
+        }
+        points
+    }
+
+    /// 1 for the opening phase, counting up as the boss takes damage.
+    pub fn phase(&self) -> usize {
+        self.phase + 1
+    }
+
+    /// Health left over all parts, from 0.0 to 1.0.
+    pub fn health_share(&self) -> f32 {
+        let max: u32 = self.parts.iter().map(|part| part.max_health).sum();
+        if max == 0 {
+            return 0.0;
+        }
+        let health: u32 = self.parts.iter().map(|part| part.health).sum();
+        health as f32 / max as f32
+    }
+
+    pub fn is_defeated(&self) -> bool {
+        self.parts.iter().all(|part| part.game_obj.destroyed)
+    }
+
+    pub fn draw(&self, renderer: &SpriteRenderer) {
+        for part in self.parts.iter().filter(|part| !part.game_obj.destroyed) {
+            part.game_obj.draw(renderer);
+
+            // health bar along the top edge of the part
+            let obj = &part.game_obj;
+            let bar = |width: f32, color: glm::TVec4<f32>| SpriteDesc {
+                color,
+                ..SpriteDesc::new(obj.position, glm::vec2(width, HEALTH_BAR_HEIGHT))
+            };
+            let share = part.health as f32 / part.max_health as f32;
+            renderer.draw(
+                &self.bar_sprite,
+                &bar(obj.size.x, glm::vec4(0.0, 0.0, 0.0, 0.7)),
+            );
+            renderer.draw(
+                &self.bar_sprite,
+                &bar(obj.size.x * share, glm::vec4(1.0 - share, share, 0.1, 1.0)),
+            );
+        }
+    }
+}
+
+// flashes a part white when the ball hits it
+fn hit_flash() -> Animation {
+    let frames = [3.0, 1.0, 3.0, 1.0]
+        .map(|brightness| Frame {
+            tint: glm::vec4(brightness, brightness, brightness, 1.0),
+            ..Frame::new([0.0, 0.0, 1.0, 1.0], 0.05)
+        })
+        .to_vec();
+    Animation::new(frames, PlayMode::Once)
+}
+
+/// Parses a boss line of a level file, which adds one part to the level's boss, placed and sized
+/// in tiles with the top left tile at `0,0`:
+///
+/// ```text
+/// boss <column>,<row> <columns>,<rows> <health>
+/// ```
+pub fn parse_boss_part(line: &str) -> Result<(glm::TVec2<f32>, glm::TVec2<f32>, u32), String> {
+    let mut parts = line.split_whitespace();
+    if parts.next() != Some("boss") {
+        return Err("not a boss line".to_string());
+    }
+    let position = parse_point(parts.next().ok_or("missing position")?)?;
+    let size = parse_point(parts.next().ok_or("missing size")?)?;
+    if size.x <= 0.0 || size.y <= 0.0 {
+        return Err("size must be positive".to_string());
+    }
+    let health = parts.next().ok_or("missing health")?;
+    let health = match health.parse::<u32>() {
+        Ok(health) if health > 0 => health,
+        _ => return Err(format!("invalid health '{}'", health)),
+    };
+    match parts.next() {
+        Some(extra) => Err(format!("unexpected '{}'", extra)),
+        None => Ok((position, size, health)),
+    }
+}
@@ -0,0 +1,132 @@
+use std::ops::RangeInclusive;
+
+use nalgebra_glm as glm;
+
+// how long a descending formation takes to drop one step
+const DESCEND_STEP_TIME: f32 = 0.3;
+
+/// How a formation of bricks moves, measured in tiles so it scales with the level.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BrickMotion {
+    /// Slides side to side up to `amplitude` tiles either way, once every `period` seconds.
+    Scroll { amplitude: f32, period: f32 },
+    /// Drops `step` tiles every `interval` seconds, Space Invaders style.
+    Descend { step: f32, interval: f32 },
+    /// Loops from its start through `points`, offsets from the start, at `speed` tiles per
+    /// second.
+    Path {
+        points: Vec<glm::TVec2<f32>>,
+        speed: f32,
+    },
+}
+
+impl BrickMotion {
+    /// Offset from the start position after `time` seconds, in tiles.
+    pub fn offset(&self, time: f32) -> glm::TVec2<f32> {
+        match self {
+            BrickMotion::Scroll { amplitude, period } => {
+                let phase = time / period * std::f32::consts::TAU;
+                glm::vec2(amplitude * phase.sin(), 0.0)
+            }
+            BrickMotion::Descend { step, interval } => {
+                let steps = (time / interval).floor();
+                let dropping = ((time - steps * interval) / DESCEND_STEP_TIME).min(1.0);
+                glm::vec2(0.0, step * (steps + dropping))
+            }
+            BrickMotion::Path { points, speed } => {
+                // the closed loop start -> points -> start
+                let mut corners = vec![glm::vec2(0.0, 0.0)];
+                corners.extend(points.iter().copied());
+                corners.push(glm::vec2(0.0, 0.0));
+                let length: f32 = corners
+                    .windows(2)
+                    .map(|w| glm::distance(&w[0], &w[1]))
+                    .sum();
+                if length <= 0.0 {
+                    return glm::vec2(0.0, 0.0);
+                }
+                let mut travelled = (time * speed).rem_euclid(length);
+                for w in corners.windows(2) {
+                    let segment = glm::distance(&w[0], &w[1]);
+                    if travelled <= segment && segment > 0.0 {
+                        return w[0] + (w[1] - w[0]) * (travelled / segment);
+                    }
+                    travelled -= segment;
+                }
+                glm::vec2(0.0, 0.0)
+            }
+        }
+    }
+}
+
+/// Parses a motion line of a level file, which applies to a row or an inclusive range of rows
+/// counted from 0 at the top:
+///
+/// ```text
+/// scroll <rows> <amplitude> <period>
+/// descend <rows> <step> <interval>
+/// path <rows> <speed> <x,y> <x,y> ...
+/// ```
+pub fn parse_motion(line: &str) -> Result<(RangeInclusive<usize>, BrickMotion), String> {
+    let mut parts = line.split_whitespace();
+    let kind = parts.next().ok_or("empty motion")?;
+    let rows = parse_rows(parts.next().ok_or("missing rows")?)?;
+    let mut number = |name: &str| -> Result<f32, String> {
+        let value = parts.next().ok_or(format!("missing {}", name))?;
+        match value.parse::<f32>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(format!("invalid {} '{}'", name, value)),
+        }
+    };
+    let motion = match kind {
+        "scroll" => {
+            let amplitude = number("amplitude")?;
+            let period = number("period")?;
+            if period <= 0.0 {
+                return Err("period must be positive".to_string());
+            }
+            BrickMotion::Scroll { amplitude, period }
+        }
+        "descend" => {
+            let step = number("step")?;
+            let interval = number("interval")?;
+            if interval <= DESCEND_STEP_TIME {
+                return Err(format!("interval must be over {}", DESCEND_STEP_TIME));
+            }
+            BrickMotion::Descend { step, interval }
+        }
+        "path" => {
+            let speed = number("speed")?;
+            let points = parts.map(parse_point).collect::<Result<Vec<_>, _>>()?;
+            if points.is_empty() {
+                return Err("path needs at least one point".to_string());
+            }
+            return Ok((rows, BrickMotion::Path { points, speed }));
+        }
+        _ => return Err(format!("unknown motion '{}'", kind)),
+    };
+    match parts.next() {
+        Some(extra) => Err(format!("unexpected '{}'", extra)),
+        None => Ok((rows, motion)),
+    }
+}
+
+fn parse_rows(rows: &str) -> Result<RangeInclusive<usize>, String> {
+    let invalid = || format!("invalid rows '{}'", rows);
+    let (first, last) = rows.split_once('-').unwrap_or((rows, rows));
+    let first = first.parse().map_err(|_| invalid())?;
+    let last = last.parse().map_err(|_| invalid())?;
+    if first > last {
+        return Err(invalid());
+    }
+    Ok(first..=last)
+}
+
+/// Parses an `x,y` pair.
+pub fn parse_point(point: &str) -> Result<glm::TVec2<f32>, String> {
+    let invalid = || format!("invalid point '{}'", point);
+    let (x, y) = point.split_once(',').ok_or_else(invalid)?;
+    let x = x.parse().map_err(|_| invalid())?;
+    let y = y.parse().map_err(|_| invalid())?;
+    Ok(glm::vec2(x, y))
+}
@@ -0,0 +1,96 @@
+use winit::keyboard::KeyCode;
+
+use crate::input::{
+    Action,
+    Input,
+    InputBindings,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ControlItem {
+    Bind(Action),
+    ResetDefaults,
+    Back,
+}
+
+/// The rebind screen reachable from the options. Selecting an action waits for the next key or
+/// gamepad button press and adds it to that action's bindings.
+pub struct ControlsMenu {
+    items: Vec<ControlItem>,
+    selected: usize,
+    waiting: bool,
+}
+
+impl ControlsMenu {
+    pub fn new() -> Self {
+        let mut items = Action::ALL.map(ControlItem::Bind).to_vec();
+        items.push(ControlItem::ResetDefaults);
+        items.push(ControlItem::Back);
+        Self {
+            items,
+            selected: 0,
+            waiting: false,
+        }
+    }
+
+    pub fn selected(&self) -> ControlItem {
+        self.items[self.selected]
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+    }
+
+    /// Whether the next key press should be bound instead of navigating the menu.
+    pub fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    pub fn start_waiting(&mut self) {
+        self.waiting = matches!(self.selected(), ControlItem::Bind(_));
+    }
+
+    /// Binds `input` to the selected action; Escape cancels. Returns whether the bindings changed.
+    pub fn capture(&mut self, bindings: &mut InputBindings, input: Input) -> bool {
+        if input == Input::Key(KeyCode::Escape) {
+            self.waiting = false;
+            return false;
+        }
+        let ControlItem::Bind(action) = self.selected() else {
+            self.waiting = false;
+            return false;
+        };
+        if !input.is_bindable() {
+            println!("{} can't be bound", input.label());
+            return false;
+        }
+        self.waiting = false;
+        bindings.bind(action, input)
+    }
+
+    /// One line per item, paired with whether it is the selected one.
+    pub fn lines(&self, bindings: &InputBindings) -> Vec<(String, bool)> {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let selected = i == self.selected;
+                let line = match item {
+                    ControlItem::Bind(action) if selected && self.waiting => {
+                        format!("{}: press a key or button...", action.label())
+                    }
+                    ControlItem::Bind(action) => {
+                        format!("{}: {}", action.label(), bindings.describe(*action))
+                    }
+                    ControlItem::ResetDefaults => "Reset to defaults".to_string(),
+                    ControlItem::Back => "Back".to_string(),
+                };
+                (line, selected)
+            })
+            .collect()
+    }
+}
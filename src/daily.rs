@@ -0,0 +1,397 @@
+use std::{
+    fmt,
+    str::FromStr,
+};
+
+use crate::{
+    level_generator::{
+        Coloring,
+        GeneratorConfig,
+        Symmetry,
+        generate,
+    },
+    rng::Rng,
+    settings::Settings,
+};
+
+/// A calendar day, in UTC so everyone gets the same challenge on the same day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        Self::from_days((seconds / 86_400) as i64)
+    }
+
+    // the civil date `days` after 1970-01-01, after Howard Hinnant's `civil_from_days`
+    fn from_days(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (year_of_era + era * 400 + i64::from(month <= 2)) as i32;
+        Self { year, month, day }
+    }
+
+    /// The seed everything about the day's challenge comes from.
+    pub fn seed(&self) -> u64 {
+        let date = self.year as u64 * 10_000 + self.month as u64 * 100 + self.day as u64;
+        Rng::new(date).next_u64()
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, '-');
+        let mut next = || parts.next().ok_or(());
+        let year = next()?.parse().map_err(|_| ())?;
+        let month = next()?.parse().map_err(|_| ())?;
+        let day = next()?.parse().map_err(|_| ())?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return Err(());
+        }
+        Ok(Self { year, month, day })
+    }
+}
+
+/// Twists on the usual rules that a daily challenge is played under.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Modifiers {
+    pub fast_ball: bool,
+    pub tiny_paddle: bool,
+    /// No capsules drop from bricks.
+    pub no_power_ups: bool,
+    /// Left steers right and right steers left.
+    pub mirrored_controls: bool,
+}
+
+impl Modifiers {
+    // each one is a coin flip, but a challenge always has at least one
+    fn roll(rng: &mut Rng) -> Self {
+        let mut modifiers = Self {
+            fast_ball: rng.chance(0.5),
+            tiny_paddle: rng.chance(0.5),
+            no_power_ups: rng.chance(0.5),
+            mirrored_controls: rng.chance(0.5),
+        };
+        if modifiers == Self::default() {
+            match rng.below(4) {
+                0 => modifiers.fast_ball = true,
+                1 => modifiers.tiny_paddle = true,
+                2 => modifiers.no_power_ups = true,
+                _ => modifiers.mirrored_controls = true,
+            }
+        }
+        modifiers
+    }
+}
+
+impl fmt::Display for Modifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [
+            (self.fast_ball, "fast ball"),
+            (self.tiny_paddle, "tiny paddle"),
+            (self.no_power_ups, "no power-ups"),
+            (self.mirrored_controls, "mirrored controls"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect::<Vec<_>>();
+        if names.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&names.join(", "))
+        }
+    }
+}
+
+/// The level and modifiers of one day's challenge, the same for everyone who plays that day.
+pub struct DailyChallenge {
+    pub date: Date,
+    pub modifiers: Modifiers,
+    pub tiles: Vec<Vec<u32>>,
+}
+
+impl DailyChallenge {
+    pub fn for_date(date: Date) -> Self {
+        let mut rng = Rng::new(date.seed());
+        let modifiers = Modifiers::roll(&mut rng);
+        let config = GeneratorConfig {
+            density: 0.55 + rng.next_f32() * 0.3,
+            symmetry: if rng.chance(0.75) {
+                Symmetry::Mirror
+            } else {
+                Symmetry::None
+            },
+            coloring: if rng.chance(0.5) {
+                Coloring::Bands(1 + rng.below(3) as usize)
+            } else {
+                Coloring::Random
+            },
+            ..GeneratorConfig::default()
+        };
+        let tiles = generate(&config, rng.next_u64());
+        Self {
+            date,
+            modifiers,
+            tiles,
+        }
+    }
+}
+
+/// The input of one update of a daily challenge: how long it was, how the paddle was steered
+/// and whether the ball was launched just before it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayFrame {
+    pub dt: f32,
+    pub axis: f32,
+    pub target_x: Option<f32>,
+    pub launch: bool,
+}
+
+/// A recorded daily challenge attempt. The game plays the same way from the same input and the
+/// settings that steer the ball and paddle, so those are enough to watch it again:
+///
+/// ```text
+/// # Breakout daily challenge replay
+/// date 2026-10-19
+/// score 420
+/// paddle_speed 500
+/// curve_ball off
+/// bounce_angles 15 75
+/// # <dt> <axis> <paddle x or -> [launch]
+/// 0.016 0 - launch
+/// 0.016 1 -
+/// 0.017 0 412.5
+/// ```
+pub struct Replay {
+    pub date: Date,
+    pub score: u32,
+    paddle_speed: f32,
+    curve_ball: bool,
+    min_bounce_angle: f32,
+    max_bounce_angle: f32,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    /// An empty replay of an attempt on `date` played with `settings`.
+    pub fn new(date: Date, settings: &Settings) -> Self {
+        Self {
+            date,
+            score: 0,
+            paddle_speed: settings.paddle_speed,
+            curve_ball: settings.curve_ball,
+            min_bounce_angle: settings.min_bounce_angle,
+            max_bounce_angle: settings.max_bounce_angle,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Puts the settings the attempt was played with back in place.
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.paddle_speed = self.paddle_speed;
+        settings.curve_ball = self.curve_ball;
+        settings.min_bounce_angle = self.min_bounce_angle;
+        settings.max_bounce_angle = self.max_bounce_angle;
+    }
+
+    /// Where the replay of the attempt on `date` is saved.
+    pub fn path(date: Date) -> String {
+        format!("daily-{}.replay", date)
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read replay {}: {}", path, err))?;
+        Self::parse(&text).map_err(|err| format!("Failed to parse replay {}: {}", path, err))
+    }
+
+    pub fn save(&self, path: &str) {
+        if let Err(err) = std::fs::write(path, self.to_string()) {
+            println!("Failed to save replay to {}: {}", path, err);
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut replay = Self::new(Date::today(), &Settings::default());
+        let mut date = None;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = || format!("line {}: '{}'", i + 1, line);
+            if let Some(value) = line.strip_prefix("date ") {
+                date = Some(value.trim().parse().map_err(|_| error())?);
+            } else if let Some(value) = line.strip_prefix("score ") {
+                replay.score = value.trim().parse().map_err(|_| error())?;
+            } else if let Some(value) = line.strip_prefix("paddle_speed ") {
+                replay.paddle_speed = value.trim().parse().map_err(|_| error())?;
+            } else if let Some(value) = line.strip_prefix("curve_ball ") {
+                replay.curve_ball = match value.trim() {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(error()),
+                };
+            } else if let Some(value) = line.strip_prefix("bounce_angles ") {
+                let (min, max) = value.trim().split_once(' ').ok_or_else(error)?;
+                replay.min_bounce_angle = min.parse().map_err(|_| error())?;
+                replay.max_bounce_angle = max.trim().parse().map_err(|_| error())?;
+            } else {
+                replay.frames.push(parse_frame(line).ok_or_else(error)?);
+            }
+        }
+        replay.date = date.ok_or("missing date")?;
+        Ok(replay)
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# Breakout daily challenge replay")?;
+        writeln!(f, "date {}", self.date)?;
+        writeln!(f, "score {}", self.score)?;
+        writeln!(f, "paddle_speed {}", self.paddle_speed)?;
+        writeln!(
+            f,
+            "curve_ball {}",
+            if self.curve_ball { "on" } else { "off" }
+        )?;
+        writeln!(
+            f,
+            "bounce_angles {} {}",
+            self.min_bounce_angle, self.max_bounce_angle
+        )?;
+        writeln!(f, "# <dt> <axis> <paddle x or -> [launch]")?;
+        for frame in &self.frames {
+            // `{}` prints floats so they read back exactly, which the replay depends on
+            write!(f, "{} {} ", frame.dt, frame.axis)?;
+            match frame.target_x {
+                Some(x) => write!(f, "{}", x)?,
+                None => write!(f, "-")?,
+            }
+            if frame.launch {
+                write!(f, " launch")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_frame(line: &str) -> Option<ReplayFrame> {
+    let mut parts = line.split_whitespace();
+    let dt = parts.next()?.parse().ok()?;
+    let axis = parts.next()?.parse().ok()?;
+    let target_x = match parts.next()? {
+        "-" => None,
+        x => Some(x.parse().ok()?),
+    };
+    let launch = match parts.next() {
+        Some("launch") => true,
+        None => false,
+        Some(_) => return None,
+    };
+    parts.next().is_none().then_some(ReplayFrame {
+        dt,
+        axis,
+        target_x,
+        launch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_days_handles_leap_years_and_month_ends() {
+        assert_eq!(Date::from_days(0), date(1970, 1, 1));
+        assert_eq!(Date::from_days(-1), date(1969, 12, 31));
+        assert_eq!(Date::from_days(30), date(1970, 1, 31));
+        assert_eq!(Date::from_days(31), date(1970, 2, 1));
+        // 2000 is a leap year, being divisible by 400, and 2100 is not
+        assert_eq!(Date::from_days(11_015), date(2000, 2, 28));
+        assert_eq!(Date::from_days(11_016), date(2000, 2, 29));
+        assert_eq!(Date::from_days(11_017), date(2000, 3, 1));
+        assert_eq!(Date::from_days(47_540), date(2100, 2, 28));
+        assert_eq!(Date::from_days(47_541), date(2100, 3, 1));
+        assert_eq!(Date::from_days(19_722), date(2023, 12, 31));
+        assert_eq!(Date::from_days(19_723), date(2024, 1, 1));
+        assert_eq!(Date::from_days(19_782), date(2024, 2, 29));
+    }
+
+    #[test]
+    fn replay_round_trips() {
+        let settings = Settings {
+            paddle_speed: 612.5,
+            curve_ball: true,
+            min_bounce_angle: 20.0,
+            max_bounce_angle: 70.0,
+            ..Settings::default()
+        };
+        let mut replay = Replay::new(date(2026, 10, 19), &settings);
+        replay.score = 420;
+        replay.frames = vec![
+            ReplayFrame {
+                dt: 1.0 / 60.0,
+                axis: 0.0,
+                target_x: None,
+                launch: true,
+            },
+            ReplayFrame {
+                dt: 0.017_3,
+                axis: -0.333_333_34,
+                target_x: Some(412.5),
+                launch: false,
+            },
+        ];
+
+        let parsed = Replay::parse(&replay.to_string()).unwrap();
+        assert_eq!(parsed.date, replay.date);
+        assert_eq!(parsed.score, 420);
+        assert_eq!(parsed.frames, replay.frames);
+        let mut applied = Settings::default();
+        parsed.apply(&mut applied);
+        assert_eq!(applied.paddle_speed, 612.5);
+        assert!(applied.curve_ball);
+        assert_eq!(applied.min_bounce_angle, 20.0);
+        assert_eq!(applied.max_bounce_angle, 70.0);
+    }
+
+    #[test]
+    fn corrupt_replay_is_an_error() {
+        assert!(Replay::parse("").is_err());
+        assert!(Replay::parse("date 2026-13-01\n").is_err());
+        assert!(Replay::parse("date 2026-10-19\ncurve_ball maybe\n").is_err());
+        assert!(Replay::parse("date 2026-10-19\n0.016 sideways -\n").is_err());
+        assert!(Replay::parse("date 2026-10-19\n0.016\n").is_err());
+        assert!(Replay::parse("\u{0}\u{1}garbage").is_err());
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date { year, month, day }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{
+    level_generator::{
+        Coloring,
+        GeneratorConfig,
+        Symmetry,
+        generate,
+    },
+    rng::Rng,
+};
+
+// rows of bricks in the level to start with, of the ENDLESS_ROWS the top half of the screen holds
+const ENDLESS_ROWS: usize = 8;
+const START_ROWS: usize = 4;
+const COLUMNS: usize = 13;
+// seconds between new rows at the start of a run, how much sooner each row comes than the one
+// before, and the shortest wait it gets down to
+const START_INTERVAL: f32 = 10.0;
+const INTERVAL_STEP: f32 = 0.25;
+const MIN_INTERVAL: f32 = 4.0;
+// rows pushed in per wave shown in the HUD
+const ROWS_PER_WAVE: u32 = 5;
+
+/// The state of an endless run: new rows of generated bricks come in from the top at shorter and
+/// shorter intervals, fuller and with more solid bricks as the run goes on.
+pub struct EndlessRun {
+    rng: Rng,
+    until_push: f32,
+    rows_pushed: u32,
+}
+
+impl EndlessRun {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            until_push: START_INTERVAL,
+            rows_pushed: 0,
+        }
+    }
+
+    /// The level to start from, a full level's worth of rows of which only the top few hold bricks.
+    pub fn first_rows(&mut self) -> Vec<Vec<u32>> {
+        let mut tiles = (0..START_ROWS)
+            .flat_map(|_| self.next_row())
+            .collect::<Vec<_>>();
+        tiles.resize(ENDLESS_ROWS, vec![0; COLUMNS]);
+        tiles
+    }
+
+    /// Advances the clock while the ball is in play. Returns the rows to push in at the top when
+    /// it is time, which is straight away once the bricks on screen are `cleared`.
+    pub fn update(&mut self, dt: f32, cleared: bool) -> Option<Vec<Vec<u32>>> {
+        self.until_push -= dt;
+        if self.until_push > 0.0 && !cleared {
+            return None;
+        }
+        self.until_push =
+            (START_INTERVAL - INTERVAL_STEP * self.rows_pushed as f32).max(MIN_INTERVAL);
+        Some(self.next_row())
+    }
+
+    /// 1 to start with, one more every few rows.
+    pub fn wave(&self) -> u32 {
+        self.rows_pushed / ROWS_PER_WAVE + 1
+    }
+
+    // a single generated row, harder the more rows came before it
+    fn next_row(&mut self) -> Vec<Vec<u32>> {
+        let progress = self.rows_pushed as f32;
+        self.rows_pushed += 1;
+        let config = GeneratorConfig {
+            columns: COLUMNS,
+            rows: 1,
+            density: (0.5 + 0.02 * progress).min(0.9),
+            solid_ratio: (0.01 * progress).min(0.2),
+            symmetry: if self.rng.chance(0.5) {
+                Symmetry::Mirror
+            } else {
+                Symmetry::None
+            },
+            coloring: Coloring::Random,
+        };
+        // the generator always leaves a breakable brick, so no row walls off the ones above it
+        generate(&config, self.rng.next_u64())
+    }
+}
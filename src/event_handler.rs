@@ -18,9 +18,13 @@ use glutin::{
 use nalgebra_glm as glm;
 use winit::{
     event::{
+        DeviceEvent,
         ElementState,
         Event,
         KeyEvent,
+        Modifiers,
+        MouseButton,
+        MouseScrollDelta,
         WindowEvent,
     },
     event_loop::EventLoopWindowTarget,
@@ -28,31 +32,107 @@ use winit::{
         KeyCode,
         PhysicalKey,
     },
-    window::Window,
+    window::{
+        CursorGrabMode,
+        Window,
+    },
 };
 
-use crate::game::Game;
+use crate::{
+    controls_menu::ControlItem,
+    game::{
+        Game,
+        GameMode,
+        GameState,
+    },
+    gamepad::{
+        GamepadButton,
+        GamepadEvent,
+        GamepadSource,
+        Gamepads,
+    },
+    input::{
+        Action,
+        Input,
+        InputBindings,
+    },
+    options_menu::OptionItem,
+    settings::{
+        Rules,
+        SETTINGS_PATH,
+        Settings,
+    },
+    viewport::Viewport,
+    window::{
+        VsyncMode,
+        WindowMode,
+        fullscreen_for,
+        swap_interval_for,
+    },
+};
 
-const PLAYER_VELOCITY: f32 = 500.0;
+// longest step the game is advanced by in one frame, in seconds
+const MAX_DELTA_TIME: f32 = 0.05;
 
 pub struct EventHandler {
-    pub current_width: u32,
-    pub current_height: u32,
+    pub viewport: Viewport,
+    // last known cursor position in game coordinates
+    cursor_world: Option<glm::TVec2<f32>>,
+    // where the cursor asked the paddle centre to go since the last frame
+    paddle_target_x: Option<f32>,
+    // motion of a grabbed cursor since the last frame, in game coordinates
+    mouse_motion_x: f32,
+    cursor_grabbed: bool,
     last_frame_time: Instant,
     delta_time: f32,
-    pressed_keys: HashSet<PhysicalKey>,
+    pressed_keys: HashSet<KeyCode>,
+    gamepad_source: Option<Box<dyn GamepadSource>>,
+    gamepads: Gamepads,
+    show_stats: bool,
+    stats_timer: f32,
+    modifiers: Modifiers,
+    // window settings as currently applied, to tell what changed in `Game::settings`
+    window_mode: WindowMode,
+    vsync: VsyncMode,
+    // what Alt+Enter switches to when leaving windowed mode
+    fullscreen_mode: WindowMode,
 }
 
 impl EventHandler {
-    pub fn new(width: u32, height: u32) -> Self {
+    pub fn new(
+        width: u32,
+        height: u32,
+        settings: &Settings,
+        gamepad_source: Option<Box<dyn GamepadSource>>,
+    ) -> Self {
+        let fullscreen_mode = match settings.window.mode {
+            WindowMode::Windowed => WindowMode::Borderless,
+            mode => mode,
+        };
+
+        let mut viewport = Viewport::new(width, height);
+        viewport.set_integer_scaling(settings.integer_scaling);
+
         Self {
-            current_width: width,
-            current_height: height,
+            viewport,
+            cursor_world: None,
+            paddle_target_x: None,
+            mouse_motion_x: 0.0,
+            cursor_grabbed: false,
             last_frame_time: Instant::now(),
             delta_time: 0.0,
             pressed_keys: HashSet::new(),
+            gamepad_source,
+            gamepads: Gamepads::default(),
+            show_stats: false,
+            stats_timer: 0.0,
+            modifiers: Modifiers::default(),
+            window_mode: settings.window.mode,
+            vsync: settings.window.vsync,
+            fullscreen_mode,
         }
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn handle_event(
         &mut self,
         event: Event<()>,
@@ -63,16 +143,23 @@ impl EventHandler {
         gl_context: &PossiblyCurrentContext,
         window: &Window,
     ) {
-        #[allow(clippy::too_many_arguments)]
         //if let Event::WindowEvent { event, .. } = event {
         match event {
             Event::AboutToWait => {
                 let current_time = Instant::now();
+                // a stalled frame (dragging the window, a breakpoint, resuming from a pause) must
+                // not turn into one huge step that moves the ball through the walls
                 self.delta_time = current_time
                     .duration_since(self.last_frame_time)
-                    .as_secs_f32();
+                    .as_secs_f32()
+                    .min(MAX_DELTA_TIME);
                 self.last_frame_time = current_time;
 
+                self.poll_gamepads(game, window);
+                if game.settings_changed {
+                    self.apply_settings(game, window, gl_surface, gl_context);
+                }
+                self.update_cursor_grab(game, window);
                 self.handle_continuous_input(game);
                 // Request a redraw to keep the game loop running
                 window.request_redraw();
@@ -93,8 +180,52 @@ impl EventHandler {
                 event: WindowEvent::Resized(physical_size),
                 ..
             } => {
+                // some platforms report minimising as a resize to nothing
+                if physical_size.width == 0 || physical_size.height == 0 {
+                    game.pause();
+                }
                 self.handle_resize(physical_size, gl, gl_surface, gl_context, window);
             }
+            Event::WindowEvent {
+                event: WindowEvent::Focused(false) | WindowEvent::Occluded(true),
+                ..
+            } => {
+                game.pause();
+                // keys released while we weren't looking never send their release event
+                self.pressed_keys.clear();
+            }
+            Event::WindowEvent {
+                event: WindowEvent::ScaleFactorChanged { .. },
+                ..
+            } => {
+                // winit has already picked the new physical size, just refit the game into it
+                self.handle_resize(window.inner_size(), gl, gl_surface, gl_context, window);
+            }
+            Event::WindowEvent {
+                event: WindowEvent::ModifiersChanged(modifiers),
+                ..
+            } => {
+                self.modifiers = modifiers;
+            }
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { position, .. },
+                ..
+            } => {
+                let cursor = self.viewport.screen_to_world(position.x, position.y);
+                self.cursor_world = Some(cursor);
+                // a grabbed cursor is driven by the raw motion below instead
+                if !self.cursor_grabbed {
+                    self.paddle_target_x = Some(cursor.x);
+                }
+            }
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } if self.cursor_grabbed => {
+                self.mouse_motion_x += delta.0 as f32
+                    * self.viewport.world_units_per_pixel()
+                    * game.settings.mouse_sensitivity;
+            }
             Event::WindowEvent {
                 event:
                     WindowEvent::KeyboardInput {
@@ -102,7 +233,10 @@ impl EventHandler {
                     },
                 ..
             } => {
-                self.handle_keyboard_input(key_event, game, window, self.delta_time);
+                self.handle_keyboard_input(key_event, game, window);
+                if game.settings_changed {
+                    self.apply_settings(game, window, gl_surface, gl_context);
+                }
             }
             Event::WindowEvent {
                 event: WindowEvent::MouseInput { state, button, .. },
@@ -114,36 +248,96 @@ impl EventHandler {
                 event: WindowEvent::MouseWheel { delta, .. },
                 ..
             } => {
-                self.handle_mouse_wheel(delta, game);
+                self.handle_mouse_wheel(delta, game, window);
             }
             _ => (),
         }
         //}
     }
 
-    fn handle_continuous_input(&self, game: &mut Game) {
-        let velocity = PLAYER_VELOCITY * self.delta_time;
+    fn handle_continuous_input(&mut self, game: &mut Game) {
+        let paddle_target_x = self.paddle_target_x.take();
+        let mouse_motion_x = std::mem::take(&mut self.mouse_motion_x);
+        if game.state != GameState::Active {
+            game.steer_player(0.0);
+            return;
+        }
 
-        if let Some(player) = &mut game.player {
-            if self
-                .pressed_keys
-                .contains(&PhysicalKey::Code(KeyCode::KeyA))
-                && player.position.x >= 0.0
-            {
-                player.position.x -= velocity;
-                if game.ball.as_ref().unwrap().stuck {
-                    game.ball.as_mut().unwrap().game_obj.position.x -= velocity;
-                }
+        let is_pressed = |input| match input {
+            Input::Key(key) => self.pressed_keys.contains(&key),
+            Input::Button(button) => self.gamepads.is_down(button),
+        };
+        let bindings = &game.settings.bindings;
+        let mut axis = 0.0;
+        if bindings.is_down(Action::MoveLeft, is_pressed) {
+            axis -= 1.0;
+        }
+        if bindings.is_down(Action::MoveRight, is_pressed) {
+            axis += 1.0;
+        }
+        // the stick scales the paddle speed with how far it is pushed
+        axis += self.gamepads.stick_x(game.settings.stick_deadzone);
+        // player two, sharing the keyboard in versus
+        let mut player_two_axis = 0.0;
+        if bindings.is_down(Action::Player2Left, is_pressed) {
+            player_two_axis -= 1.0;
+        }
+        if bindings.is_down(Action::Player2Right, is_pressed) {
+            player_two_axis += 1.0;
+        }
+        game.steer_player(axis);
+        game.steer_player_two(player_two_axis);
+
+        if game.settings.mouse_control {
+            if let Some(target_x) = paddle_target_x {
+                game.move_player_to(target_x);
             }
-            if self
-                .pressed_keys
-                .contains(&PhysicalKey::Code(KeyCode::KeyD))
-                && player.position.x <= game.width as f32 - player.size.x
-            {
-                player.position.x += velocity;
-                if game.ball.as_ref().unwrap().stuck {
-                    game.ball.as_mut().unwrap().game_obj.position.x += velocity;
-                }
+            if mouse_motion_x != 0.0 {
+                game.move_player_to(game.player_center_x() + mouse_motion_x);
+            }
+        }
+    }
+
+    // feeds gamepad buttons through the same actions as the keyboard
+    fn poll_gamepads(&mut self, game: &mut Game, window: &Window) {
+        let Some(source) = &mut self.gamepad_source else {
+            return;
+        };
+        for event in source.poll(self.delta_time) {
+            self.gamepads.handle(event);
+            if let GamepadEvent::Pressed(_, button) = event {
+                self.handle_input_pressed(Input::Button(button), false, game, window);
+            }
+        }
+    }
+
+    // confines and hides the cursor while playing with the mouse, if the player asked for it
+    fn update_cursor_grab(&mut self, game: &mut Game, window: &Window) {
+        let grab = game.state == GameState::Active
+            && game.settings.mouse_control
+            && game.settings.grab_cursor
+            && window.has_focus();
+        if grab == self.cursor_grabbed {
+            return;
+        }
+
+        let result = if grab {
+            // not every platform can confine, locking in place works just as well for us
+            window
+                .set_cursor_grab(CursorGrabMode::Confined)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Locked))
+        } else {
+            window.set_cursor_grab(CursorGrabMode::None)
+        };
+        match result {
+            Ok(()) => {
+                window.set_cursor_visible(!grab);
+                self.cursor_grabbed = grab;
+            }
+            Err(err) => {
+                // turn it off for this session rather than retrying every frame
+                println!("Could not grab the cursor, disabling it: {}", err);
+                game.settings.grab_cursor = false;
             }
         }
     }
@@ -153,7 +347,7 @@ impl EventHandler {
     }
 
     fn handle_redraw_requested(
-        &self,
+        &mut self,
         game: &mut Game,
         gl: &Rc<Context>,
         gl_surface: &Surface<glutin::surface::WindowSurface>,
@@ -161,13 +355,30 @@ impl EventHandler {
     ) {
         game.update(self.delta_time);
 
-        unsafe {
-            gl.clear_color(0.2, 0.3, 0.3, 1.0);
-            gl.clear(glow::COLOR_BUFFER_BIT);
-        }
+        self.viewport.apply(gl);
 
         game.render();
         gl_surface.swap_buffers(gl_context).unwrap();
+
+        if self.show_stats {
+            self.stats_timer += self.delta_time;
+            if self.stats_timer >= 1.0 {
+                let stats = game.render_stats();
+                let cursor = match self.cursor_world {
+                    Some(cursor) => format!("{:.0}, {:.0}", cursor.x, cursor.y),
+                    None => "-".to_string(),
+                };
+                game.show_stats(Some(format!(
+                    "FPS: {:.0}   Draw calls: {}   Vertices: {}   Sprites: {}   Cursor: {}",
+                    1.0 / self.delta_time.max(f32::EPSILON),
+                    stats.draw_calls,
+                    stats.vertices,
+                    stats.sprites,
+                    cursor
+                )));
+                self.stats_timer = 0.0;
+            }
+        }
     }
 
     fn handle_resize(
@@ -178,12 +389,11 @@ impl EventHandler {
         gl_context: &PossiblyCurrentContext,
         window: &Window,
     ) {
-        self.current_width = physical_size.width;
-        self.current_height = physical_size.height;
+        // keep the game's aspect ratio, the GL viewport itself is set up before every frame
+        self.viewport
+            .resize(physical_size.width, physical_size.height);
 
-        // Update OpenGL viewport
         unsafe {
-            gl.viewport(0, 0, self.current_width as i32, self.current_height as i32);
             gl.enable(glow::BLEND);
             gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
         }
@@ -191,98 +401,284 @@ impl EventHandler {
         // Resize the surface
         gl_surface.resize(
             gl_context,
-            std::num::NonZeroU32::new(self.current_width.max(1)).unwrap(),
-            std::num::NonZeroU32::new(self.current_height.max(1)).unwrap(),
+            std::num::NonZeroU32::new(physical_size.width.max(1)).unwrap(),
+            std::num::NonZeroU32::new(physical_size.height.max(1)).unwrap(),
         );
 
         // Request a redraw to update the scene with new dimensions
         window.request_redraw();
     }
 
-    fn handle_keyboard_input(
-        &mut self,
-        key_event: KeyEvent,
-        game: &mut Game,
-        window: &Window,
-        dt: f32,
-    ) {
+    fn handle_keyboard_input(&mut self, key_event: KeyEvent, game: &mut Game, window: &Window) {
+        let PhysicalKey::Code(key) = key_event.physical_key else {
+            return;
+        };
+
         if key_event.state.is_pressed() {
-            self.pressed_keys.insert(key_event.physical_key);
+            self.pressed_keys.insert(key);
 
-            let velocity = PLAYER_VELOCITY * dt;
-            match key_event.physical_key {
-                PhysicalKey::Code(KeyCode::KeyW)
-                    if !game.keys_processed[KeyCode::KeyW as usize] =>
-                {
-                    game.current_level = (game.current_level + 1) % game.levels.len();
-                    game.keys_processed[KeyCode::KeyW as usize] = true;
-                    window.request_redraw();
+            // available in every state
+            match key {
+                KeyCode::F3 if !key_event.repeat => {
+                    self.show_stats = !self.show_stats;
+                    // the first line goes up with the next frame, then once a second
+                    self.stats_timer = 1.0;
+                    if !self.show_stats {
+                        game.show_stats(None);
+                    }
+                    return;
                 }
-                PhysicalKey::Code(KeyCode::KeyS)
-                    if !game.keys_processed[KeyCode::KeyS as usize] =>
+                KeyCode::Enter | KeyCode::NumpadEnter
+                    if !key_event.repeat && self.modifiers.state().alt_key() =>
                 {
-                    if game.current_level > 0 {
-                        game.current_level -= 1;
-                    } else {
-                        game.current_level = game.levels.len() - 1;
-                    }
-                    game.keys_processed[KeyCode::KeyS as usize] = true;
-                    window.request_redraw();
+                    game.settings.window.mode = match game.settings.window.mode {
+                        WindowMode::Windowed => self.fullscreen_mode,
+                        _ => WindowMode::Windowed,
+                    };
+                    game.settings_changed = true;
+                    return;
                 }
-                PhysicalKey::Code(KeyCode::Space) => {
-                    game.ball.as_mut().unwrap().stuck = false;
-                    game.ball.as_mut().unwrap().game_obj.velocity = glm::vec2(100.0, -350.0);
+                KeyCode::F4 if !key_event.repeat => {
+                    game.settings.integer_scaling = !game.settings.integer_scaling;
+                    game.settings_changed = true;
+                    return;
                 }
-                PhysicalKey::Code(KeyCode::KeyA) => {
-                    if let Some(player) = &mut game.player
-                        && player.position.x >= 0.0
-                    {
-                        player.position.x -= velocity;
-                        if game.ball.as_ref().unwrap().stuck {
-                            game.ball.as_mut().unwrap().game_obj.position.x -= velocity;
-                        }
-                        //window.request_redraw();
-                        //dbg!(player.position.x);
+                _ => {}
+            }
+
+            self.handle_input_pressed(Input::Key(key), key_event.repeat, game, window);
+        } else if key_event.state == ElementState::Released {
+            self.pressed_keys.remove(&key);
+        }
+    }
+
+    // turns a key or button press into the actions bound to it for the current state
+    fn handle_input_pressed(&self, input: Input, repeat: bool, game: &mut Game, window: &Window) {
+        if game.state == GameState::Controls {
+            self.handle_controls_input(input, repeat, game);
+            return;
+        }
+
+        // gameplay and menu actions may share inputs, only the ones for this state count
+        let in_game = matches!(game.state, GameState::Active | GameState::Paused);
+        for action in game.settings.bindings.actions_for(input) {
+            if action.is_menu() == in_game {
+                continue;
+            }
+            match game.state {
+                GameState::Active => self.handle_active_action(action, repeat, game, window),
+                GameState::Paused => {
+                    if action == Action::Pause && !repeat {
+                        game.state = GameState::Active;
                     }
                 }
-                PhysicalKey::Code(KeyCode::KeyD) => {
-                    if let Some(player) = &mut game.player
-                        && player.position.x <= game.width as f32 - player.size.x
-                    {
-                        player.position.x += velocity;
-                        if game.ball.as_ref().unwrap().stuck {
-                            game.ball.as_mut().unwrap().game_obj.position.x += velocity;
-                        }
-                        //window.request_redraw();
-                        //dbg!(player.position.x);
+                GameState::Menu => self.handle_menu_action(action, repeat, game, window),
+                GameState::Options => self.handle_options_action(action, game),
+                GameState::Win | GameState::GameOver => {
+                    if action == Action::Confirm && !repeat {
+                        game.state = GameState::Menu;
                     }
                 }
+                GameState::Controls => {}
+            }
+        }
+    }
+
+    fn handle_active_action(&self, action: Action, repeat: bool, game: &mut Game, window: &Window) {
+        match action {
+            Action::NextLevel | Action::PreviousLevel if !repeat => {
+                self.handle_level_select(action, game, window);
+            }
+            Action::Launch => game.launch_ball(),
+            Action::Player2Launch => game.launch_player_two_ball(),
+            Action::Pause if !repeat => game.pause(),
+            Action::Restart if !repeat => game.restart_level(),
+            _ => {}
+        }
+    }
+
+    fn handle_menu_action(&self, action: Action, repeat: bool, game: &mut Game, window: &Window) {
+        // a network game starts by itself once the other side is there
+        if game.is_online() {
+            return;
+        }
+        match action {
+            Action::Confirm if !repeat => game.start(),
+            Action::Options if !repeat => {
+                game.state = GameState::Options;
+            }
+            Action::MenuUp if !repeat => {
+                self.handle_level_select(Action::NextLevel, game, window);
+            }
+            Action::MenuDown if !repeat => {
+                self.handle_level_select(Action::PreviousLevel, game, window);
+            }
+            Action::MenuLeft if !repeat => game.cycle_mode(-1),
+            Action::MenuRight if !repeat => game.cycle_mode(1),
+            _ => {}
+        }
+    }
+
+    fn handle_options_action(&self, action: Action, game: &mut Game) {
+        match action {
+            Action::MenuUp => game.options_menu.select_previous(),
+            Action::MenuDown => game.options_menu.select_next(),
+            Action::MenuLeft => {
+                game.settings_changed |= game.options_menu.adjust(&mut game.settings, -1);
+            }
+            Action::MenuRight => {
+                game.settings_changed |= game.options_menu.adjust(&mut game.settings, 1);
+            }
+            Action::Confirm => match game.options_menu.selected() {
+                OptionItem::Controls => game.state = GameState::Controls,
+                OptionItem::Back => game.state = GameState::Menu,
+                _ => game.settings_changed |= game.options_menu.adjust(&mut game.settings, 1),
+            },
+            Action::Back => game.state = GameState::Menu,
+            _ => {}
+        }
+    }
+
+    fn handle_controls_input(&self, input: Input, repeat: bool, game: &mut Game) {
+        let menu = &mut game.controls_menu;
+        if menu.is_waiting() {
+            if !repeat {
+                game.settings_changed |= menu.capture(&mut game.settings.bindings, input);
+            }
+            return;
+        }
+
+        if let (
+            Input::Key(KeyCode::Delete | KeyCode::Backspace) | Input::Button(GamepadButton::West),
+            ControlItem::Bind(action),
+        ) = (input, menu.selected())
+        {
+            game.settings_changed |= game.settings.bindings.clear(action);
+            return;
+        }
+
+        for action in game.settings.bindings.actions_for(input) {
+            match action {
+                Action::MenuUp => menu.select_previous(),
+                Action::MenuDown => menu.select_next(),
+                Action::Confirm if !repeat => match menu.selected() {
+                    ControlItem::Bind(_) => menu.start_waiting(),
+                    ControlItem::ResetDefaults => {
+                        game.settings.bindings = InputBindings::default();
+                        game.settings_changed = true;
+                    }
+                    ControlItem::Back => game.state = GameState::Options,
+                },
+                Action::Back => game.state = GameState::Options,
                 _ => {}
             }
-        } else if key_event.state == ElementState::Released {
-            self.pressed_keys.remove(&key_event.physical_key);
-            match key_event.physical_key {
-                PhysicalKey::Code(KeyCode::KeyW) => {
-                    game.keys_processed[KeyCode::KeyW as usize] = false;
-                }
-                PhysicalKey::Code(KeyCode::KeyS) => {
-                    game.keys_processed[KeyCode::KeyS as usize] = false;
+        }
+    }
+
+    fn handle_level_select(&self, action: Action, game: &mut Game, window: &Window) {
+        // classic rules always play the arcade wall, endless runs its own level and time attack
+        // runs them all in order; co-op picks its level like the standard game, except
+        // online where both sides play the host's
+        let selectable = match game.mode {
+            GameMode::Standard => game.settings.rules == Rules::Modern,
+            GameMode::Coop => !game.is_online(),
+            _ => false,
+        };
+        if !selectable {
+            return;
+        }
+        match action {
+            Action::NextLevel => {
+                game.current_level = (game.current_level + 1) % game.levels.len();
+                window.request_redraw();
+            }
+            Action::PreviousLevel => {
+                if game.current_level > 0 {
+                    game.current_level -= 1;
+                } else {
+                    game.current_level = game.levels.len() - 1;
                 }
-                _ => {}
+                window.request_redraw();
             }
+            _ => {}
         }
     }
 
-    fn handle_mouse_input(
-        &self,
-        state: winit::event::ElementState,
-        button: winit::event::MouseButton,
+    // brings the window, swap interval and viewport in line with `game.settings` and saves them
+    fn apply_settings(
+        &mut self,
         game: &mut Game,
+        window: &Window,
+        gl_surface: &Surface<glutin::surface::WindowSurface>,
+        gl_context: &PossiblyCurrentContext,
     ) {
-        // TODO: Implement mouse input handling
+        game.settings_changed = false;
+        let settings = &game.settings;
+
+        if settings.window.mode != self.window_mode {
+            if settings.window.mode != WindowMode::Windowed {
+                self.fullscreen_mode = settings.window.mode;
+            }
+            window.set_fullscreen(fullscreen_for(
+                settings.window.mode,
+                window.current_monitor(),
+                settings.window.width,
+                settings.window.height,
+            ));
+            self.window_mode = settings.window.mode;
+        }
+
+        if settings.window.vsync != self.vsync {
+            if let Err(err) =
+                gl_surface.set_swap_interval(gl_context, swap_interval_for(settings.window.vsync))
+            {
+                println!(
+                    "Could not set vsync to {:?}: {}",
+                    settings.window.vsync, err
+                );
+            }
+            self.vsync = settings.window.vsync;
+        }
+
+        if settings.integer_scaling != self.viewport.integer_scaling() {
+            self.viewport.set_integer_scaling(settings.integer_scaling);
+            window.request_redraw();
+        }
+
+        settings.save(SETTINGS_PATH);
+    }
+
+    fn handle_mouse_input(&self, state: ElementState, button: MouseButton, game: &mut Game) {
+        if state == ElementState::Pressed
+            && button == MouseButton::Left
+            && game.state == GameState::Active
+            && game.settings.mouse_control
+        {
+            game.launch_ball();
+        }
     }
 
-    fn handle_mouse_wheel(&self, delta: winit::event::MouseScrollDelta, game: &mut Game) {
-        // TODO: Implement mouse wheel handling for zoom
+    // scrolls through whatever list the current screen shows
+    fn handle_mouse_wheel(&self, delta: MouseScrollDelta, game: &mut Game, window: &Window) {
+        let up = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y > 0.0,
+            MouseScrollDelta::PixelDelta(position) => position.y > 0.0,
+        };
+        match game.state {
+            GameState::Menu => {
+                let action = if up {
+                    Action::NextLevel
+                } else {
+                    Action::PreviousLevel
+                };
+                self.handle_level_select(action, game, window);
+            }
+            GameState::Options if up => game.options_menu.select_previous(),
+            GameState::Options => game.options_menu.select_next(),
+            GameState::Controls if game.controls_menu.is_waiting() => {}
+            GameState::Controls if up => game.controls_menu.select_previous(),
+            GameState::Controls => game.controls_menu.select_next(),
+            _ => {}
+        }
     }
 }
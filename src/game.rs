@@ -1,39 +1,233 @@
-use std::{
-    collections,
-    rc::Rc,
-};
+use std::rc::Rc;
 
 use glow::Context;
 use lazy_static::lazy_static;
 use nalgebra_glm as glm;
 
 use crate::{
+    animation::{
+        Animation,
+        Frame,
+        PlayMode,
+    },
     ball_object::BallObject,
+    controls_menu::ControlsMenu,
+    daily::{
+        DailyChallenge,
+        Date,
+        Modifiers,
+        Replay,
+        ReplayFrame,
+    },
+    endless::EndlessRun,
     game_level::GameLevel,
     game_object::GameObject,
+    input::Action,
+    level_generator::{
+        GeneratorConfig,
+        generate,
+    },
+    netplay::{
+        NetSession,
+        NetStatus,
+        Simulation,
+        TICK,
+        TickInput,
+        Transport,
+    },
+    options_menu::OptionsMenu,
+    records::{
+        RECORDS_PATH,
+        Records,
+    },
+    projectile::{
+        Projectile,
+        ProjectileKind,
+    },
     resource_manager::{
-        self,
         ResourceManager,
+        SHATTER_FRAMES,
+    },
+    rng::Rng,
+    settings::{
+        Difficulty,
+        DifficultyProfile,
+        Rules,
+        Settings,
+    },
+    spectator::{
+        BallSnapshot,
+        BrickSnapshot,
+        Snapshot,
+        SpectatorServer,
+    },
+    speed_ramp::SpeedRamp,
+    sprite_renderer::{
+        RenderStats,
+        SpriteDesc,
+        SpriteRenderer,
+    },
+    text_renderer::TextRenderer,
+    time_attack::{
+        TimeAttack,
+        format_delta,
+        format_time,
+    },
+    two_player::{
+        PLAYER_ONE,
+        PLAYER_TWO,
+        TwoPlayer,
+        field_tiles,
+        player_name,
     },
-    sprite_renderer::SpriteRenderer,
 };
 
 pub type Collision = (bool, Direction, glm::TVec2<f32>);
 
 static ROOT_PATH: &str = "C:/Users/Osama Awad/RustroverProjects/breakout-rs";
 
+const LEVEL_FILES: [&str; 6] = [
+    "resources/levels/one.lvl",
+    "resources/levels/two.lvl",
+    "resources/levels/three.lvl",
+    "resources/levels/four.lvl",
+    "resources/levels/five.lvl",
+    "resources/levels/six.lvl",
+];
+// after the level files comes a generated level, a new layout every game
+const GENERATED_LEVEL: usize = LEVEL_FILES.len();
+// the arcade wall, played twice per game under classic rules
+const CLASSIC_LEVEL_FILE: &str = "resources/levels/classic.lvl";
+const CLASSIC_SCREENS: u32 = 2;
+// the best time attack run over every level file is kept under this key
+const CAMPAIGN_RECORD: &str = "campaign";
+
 const BALL_RADIUS: f32 = 12.5;
+// how quickly the paddle picks up and sheds speed, in pixels per second squared
+const PADDLE_ACCELERATION: f32 = 6000.0;
+const PADDLE_DECELERATION: f32 = 9000.0;
+// share of the paddle's velocity added to the ball's at impact
+const PADDLE_ENGLISH: f32 = 0.35;
+// spin the ball picks up per pixel per second of paddle velocity
+const PADDLE_SPIN: f32 = 0.01;
+const MAX_SPIN: f32 = 6.0;
+// sideways share of the ball's speed when it hits the very edge of the paddle
+const PADDLE_DEFLECTION: f32 = 0.55;
+// each shrinking shot that hits the paddle narrows it this much, down to the minimum, until the
+// next life
+const PROJECTILE_SHRINK: f32 = 0.8;
+const MIN_PADDLE_SCALE: f32 = 0.5;
+const CAPSULE_SIZE: (f32, f32) = (60.0, 20.0);
+const CAPSULE_SPEED: f32 = 150.0;
+const LASER_SIZE: (f32, f32) = (4.0, 14.0);
+const LASER_SPEED: f32 = 700.0;
+// seconds between laser shots
+const LASER_COOLDOWN: f32 = 0.35;
+// how long catch mode holds the ball before letting go by itself
+const CATCH_TIMEOUT: f32 = 3.0;
+// an endless run is over once the bricks come down this close to the bottom of the screen
+const PADDLE_ZONE_HEIGHT: f32 = 80.0;
+// what the daily challenge modifiers do to the ball speed and paddle width
+const FAST_BALL_SPEEDUP: f32 = 1.35;
+const TINY_PADDLE_SCALE: f32 = 0.6;
+// the versus field takes up this share of the screen's height, centred between the paddles
+const VERSUS_FIELD_SHARE: f32 = 0.375;
+
+// player two's paddle, told apart from player one's
+const PLAYER_TWO_COLOR: glm::TVec3<f32> = glm::TVec3::new(0.6, 0.8, 1.0);
 
 lazy_static! {
-    static ref INITIAL_BALL_VELOCITY: glm::TVec2<f32> = glm::vec2(100.0, -350.0);
+    // the ball leaves a fresh paddle up and slightly to the right
+    static ref LAUNCH_DIRECTION: glm::TVec2<f32> = glm::normalize(&glm::vec2(100.0, -350.0));
     static ref PLAYER_SIZE: glm::TVec2<f32> = glm::vec2(100.0, 20.0);
 }
 
-#[derive(PartialEq)]
-enum GameState {
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameState {
     Active,
+    Paused,
     Menu,
+    Options,
+    Controls,
     Win,
+    // an endless run has ended
+    GameOver,
+}
+
+/// What is played, picked on the menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameMode {
+    /// The levels, under the chosen rules.
+    Standard,
+    /// Generated rows of bricks pushed in from the top until they reach the paddle.
+    Endless,
+    /// The level files in order against the clock.
+    TimeAttack,
+    /// A generated level and modifiers picked by the date, scored once a day.
+    Daily,
+    /// Two players on one keyboard, one paddle at the bottom and one at the top, bricks between.
+    Versus,
+    /// Two players on one keyboard, side by side along the bottom, sharing lives through the
+    /// levels.
+    Coop,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 6] = [
+        GameMode::Standard,
+        GameMode::Endless,
+        GameMode::TimeAttack,
+        GameMode::Daily,
+        GameMode::Versus,
+        GameMode::Coop,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameMode::Standard => "Standard",
+            GameMode::Endless => "Endless",
+            GameMode::TimeAttack => "Time attack",
+            GameMode::Daily => "Daily challenge",
+            GameMode::Versus => "Versus",
+            GameMode::Coop => "Co-op",
+        }
+    }
+}
+
+/// Arkanoid-style paddle modes, picked up from capsules and kept until the next life.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PaddleMode {
+    Normal,
+    /// The launch key fires a pair of lasers that destroy bricks.
+    Laser,
+    /// The paddle holds the ball until the launch key or a timeout lets it go.
+    Catch,
+}
+
+impl PaddleMode {
+    pub fn color(&self) -> glm::TVec3<f32> {
+        match self {
+            PaddleMode::Normal => glm::vec3(1.0, 1.0, 1.0),
+            PaddleMode::Laser => glm::vec3(1.0, 0.45, 0.45),
+            PaddleMode::Catch => glm::vec3(0.45, 1.0, 0.45),
+        }
+    }
+}
+
+/// What a network game rolls back to when a guess of the other side's input was wrong: the
+/// parts of the game that play out from the input.
+pub struct NetState {
+    state: GameState,
+    lives: u32,
+    score: u32,
+    two_player: TwoPlayer,
+    player: GameObject,
+    player_two: GameObject,
+    ball: BallObject,
+    level: GameLevel,
+    speed_ramp: SpeedRamp,
+    projectiles: Vec<Projectile>,
+    paddle_scale: f32,
 }
 
 #[derive(PartialEq)]
@@ -47,33 +241,136 @@ pub enum Direction {
 pub struct Game {
     resource_manager: ResourceManager,
     gl: Rc<Context>,
-    state: GameState,
+    pub state: GameState,
+    pub mode: GameMode,
+    pub settings: Settings,
+    // set whenever `settings` changes so the window and viewport can follow
+    pub settings_changed: bool,
+    pub options_menu: OptionsMenu,
+    pub controls_menu: ControlsMenu,
     pub width: u32,
     pub height: u32,
     pub levels: Vec<GameLevel>,
+    classic_level: GameLevel,
+    endless_level: GameLevel,
+    endless: EndlessRun,
+    time_attack: TimeAttack,
+    daily: DailyChallenge,
+    daily_level: GameLevel,
+    // whether this daily challenge attempt is the day's scored one, rather than practice
+    daily_scored: bool,
+    // the input of the daily challenge attempt so far
+    replay: Replay,
+    // a replay being watched instead of played, and the next frame of it
+    playback: Option<Replay>,
+    playback_frame: usize,
+    // the ball was launched since the last update
+    launched: bool,
+    two_player: TwoPlayer,
+    versus_level: GameLevel,
+    // the network game this is one side of, while it is set up or played
+    net: Option<NetSession<Game>>,
+    // whether this is a network game, and the seed both sides generate levels from
+    online: bool,
+    net_seed: u64,
+    // where the game is streamed to watchers, if anywhere
+    spectators: Option<SpectatorServer>,
+    // the performance line toggled with F3, drawn over everything else
+    stats_line: Option<String>,
+    records: Records,
+    // whether the score that ended the last game beat the high score
+    new_high_score: bool,
     renderer: Option<Box<SpriteRenderer>>,
+    text: Option<Box<TextRenderer>>,
     pub current_level: usize,
-    pub keys_processed: [bool; 1024],
+    // what the generated level was made from
+    generated_seed: u64,
     pub player: Option<Box<GameObject>>,
+    // player two's paddle, along the top in versus and beside player one's in co-op
+    player_two: Option<Box<GameObject>>,
+    // how hard player two pushes their paddle, like `paddle_axis`
+    player_two_axis: f32,
     pub ball: Option<Box<BallObject>>,
     pub lives: u32,
+    pub score: u32,
+    // which pass through the classic wall this is, from 1
+    classic_screen: u32,
+    // classic rules halve the paddle once the ball reaches the ceiling
+    paddle_shrunk: bool,
+    speed_ramp: SpeedRamp,
+    // -1.0 (full speed left) to 1.0 (full speed right) from the keys or stick
+    paddle_axis: f32,
+    // where the mouse put the paddle's centre since the last update
+    paddle_target_x: Option<f32>,
+    projectiles: Vec<Projectile>,
+    // what projectiles have left of the paddle's width
+    paddle_scale: f32,
+    paddle_mode: PaddleMode,
+    // seconds until the laser can fire again
+    laser_cooldown: f32,
+    // seconds until a caught ball lets go, while catch mode holds it
+    catch_timer: Option<f32>,
+    // bricks destroyed towards the next capsule, and which mode it holds
+    capsule_count: u32,
+    next_capsule: PaddleMode,
 }
 
 impl Game {
-    pub fn new(gl: Rc<Context>, width: u32, height: u32) -> Self {
+    pub fn new(gl: Rc<Context>, width: u32, height: u32, settings: Settings) -> Self {
         Self {
             resource_manager: ResourceManager::new(gl.clone()),
             gl,
-            state: GameState::Active,
+            state: GameState::Menu,
+            mode: GameMode::Standard,
+            lives: settings.difficulty.profile().lives,
+            settings,
+            settings_changed: false,
+            options_menu: OptionsMenu::new(),
+            controls_menu: ControlsMenu::new(),
             width,
             height,
             levels: Vec::new(),
+            classic_level: GameLevel::new(),
+            endless_level: GameLevel::new(),
+            endless: EndlessRun::new(0),
+            time_attack: TimeAttack::new(),
+            daily: DailyChallenge::for_date(Date::today()),
+            daily_level: GameLevel::new(),
+            daily_scored: false,
+            replay: Replay::new(Date::today(), &Settings::default()),
+            playback: None,
+            playback_frame: 0,
+            launched: false,
+            two_player: TwoPlayer::new(0),
+            versus_level: GameLevel::new(),
+            net: None,
+            online: false,
+            net_seed: 0,
+            spectators: None,
+            stats_line: None,
+            records: Records::load(RECORDS_PATH),
+            new_high_score: false,
             renderer: None,
+            text: None,
             current_level: 0,
-            keys_processed: [false; 1024],
+            generated_seed: 0,
             player: None,
+            player_two: None,
+            player_two_axis: 0.0,
             ball: None,
-            lives: 3,
+            score: 0,
+            classic_screen: 1,
+            paddle_shrunk: false,
+            speed_ramp: SpeedRamp::new(),
+            paddle_axis: 0.0,
+            paddle_target_x: None,
+            projectiles: Vec::new(),
+            paddle_scale: 1.0,
+            paddle_mode: PaddleMode::Normal,
+            laser_cooldown: 0.0,
+            catch_timer: None,
+            capsule_count: 0,
+            next_capsule: PaddleMode::Laser,
         }
     }
 
@@ -94,69 +391,41 @@ impl Game {
             format!("{ROOT_PATH}/resources/textures/background.jpg").as_str(),
             "background",
         );
-        self.resource_manager.load_texture_from_file(
-            format!("{ROOT_PATH}/resources/textures/block.png").as_str(),
-            "block",
-        );
-        self.resource_manager.load_texture_from_file(
-            format!("{ROOT_PATH}/resources/textures/block_solid.png").as_str(),
-            "block_solid",
-        );
+        self.resource_manager
+            .load_atlas_from_dir(format!("{ROOT_PATH}/resources/textures").as_str());
 
-        self.resource_manager.load_texture_from_file(
-            format!("{ROOT_PATH}/resources/textures/paddle.png").as_str(),
-            "paddle",
-        );
-
-        self.resource_manager.load_texture_from_file(
-            format!("{ROOT_PATH}/resources/textures/awesomeface.png").as_str(),
-            "face",
-        );
-
-        let mut game_level1 = GameLevel::new();
-        game_level1.load(
-            "resources/levels/one.lvl",
-            self.width,
-            self.height / 2,
-            &self.resource_manager,
-        );
-        let mut game_level2 = GameLevel::new();
-        game_level2.load(
-            "resources/levels/two.lvl",
-            self.width,
-            self.height / 2,
-            &self.resource_manager,
-        );
-        let mut game_level3 = GameLevel::new();
-        game_level3.load(
-            "resources/levels/three.lvl",
-            self.width,
-            self.height / 2,
-            &self.resource_manager,
-        );
-        let mut game_level4 = GameLevel::new();
-        game_level4.load(
-            "resources/levels/four.lvl",
+        for file in LEVEL_FILES {
+            let mut level = GameLevel::new();
+            level.load(file, self.width, self.height / 2, &self.resource_manager);
+            self.levels.push(level);
+        }
+        self.levels.push(GameLevel::new());
+        self.generate_level();
+        self.classic_level.load(
+            CLASSIC_LEVEL_FILE,
             self.width,
             self.height / 2,
             &self.resource_manager,
         );
 
-        self.levels.push(game_level1);
-        self.levels.push(game_level2);
-        self.levels.push(game_level3);
-        self.levels.push(game_level4);
-
         let renderer = SpriteRenderer::new(self.gl.clone(), shader);
         self.renderer = Some(Box::new(renderer));
 
+        let mut text = TextRenderer::new(self.gl.clone());
+        text.load(
+            format!("{ROOT_PATH}/resources/fonts/OCRAEXT.TTF").as_str(),
+            24.0,
+        );
+        self.text = Some(Box::new(text));
+
+        let player_size = self.player_size();
         let player_pos = glm::vec2(
-            self.width as f32 / 2.0 - PLAYER_SIZE.x / 2.0,
-            self.height as f32 - PLAYER_SIZE.y,
+            self.width as f32 / 2.0 - player_size.x / 2.0,
+            self.height as f32 - player_size.y,
         );
         let player = GameObject::new(
             player_pos,
-            *PLAYER_SIZE,
+            player_size,
             glm::vec2(0.0, 0.0),
             self.resource_manager.get_texture("paddle"),
             glm::vec3(1.0, 1.0, 1.0),
@@ -164,12 +433,20 @@ impl Game {
 
         let player = Box::new(player);
         self.player = Some(player);
+        let player_two = GameObject::new(
+            glm::vec2(player_pos.x, 0.0),
+            player_size,
+            glm::vec2(0.0, 0.0),
+            self.resource_manager.get_texture("paddle"),
+            PLAYER_TWO_COLOR,
+        );
+        self.player_two = Some(Box::new(player_two));
 
         let ball_pos =
-            player_pos + glm::vec2(PLAYER_SIZE.x / 2.0 - BALL_RADIUS, -BALL_RADIUS * 2.0);
+            player_pos + glm::vec2(player_size.x / 2.0 - BALL_RADIUS, -BALL_RADIUS * 2.0);
         let ball = BallObject::new(
             ball_pos,
-            self.resource_manager.get_texture("face"),
+            self.resource_manager.get_texture("awesomeface"),
             BALL_RADIUS,
         );
 
@@ -180,23 +457,141 @@ impl Game {
     }
 
     pub fn update(&mut self, dt: f32) {
-        self.ball.as_mut().unwrap().move_ball(dt, self.width);
+        if self.online {
+            self.update_net(dt);
+        } else if self.state != GameState::Paused {
+            let dt = if self.mode == GameMode::Daily && self.state == GameState::Active {
+                self.replay_frame(dt)
+            } else {
+                dt
+            };
+            self.step(dt);
+        }
+        if let Some(mut spectators) = self.spectators.take() {
+            spectators.update(|| self.snapshot());
+            self.spectators = Some(spectators);
+        }
+    }
+
+    // advances the game by `dt`, whether the frame or a network game's tick drives it
+    fn step(&mut self, dt: f32) {
+        self.update_player(dt);
+        let versus = self.mode == GameMode::Versus;
+        if self.two_paddles() {
+            self.update_player_two(dt);
+        }
+        let hit_ceiling = self.ball.as_mut().unwrap().move_ball(
+            dt,
+            self.width,
+            self.settings.curve_ball,
+            !versus,
+        );
+        if hit_ceiling && self.classic() && !self.paddle_shrunk {
+            self.shrink_player();
+        }
+        let floor = self.height as f32 - PADDLE_ZONE_HEIGHT;
+        self.level_mut().update(dt, floor);
+        // rows that come down to the paddle cost a life and go back up, endless has its own end
+        // for that; the descent stops them within a pixel of the line
+        if self.mode != GameMode::Endless
+            && self.state == GameState::Active
+            && self.level().bricks_bottom() >= floor - 1.0
+        {
+            self.level_mut().rewind_motion();
+            self.lose_life();
+        }
+        self.update_paddle_mode(dt);
+        // bosses hold their fire until the ball is in play
+        if self.state == GameState::Active
+            && (!self.ball.as_ref().unwrap().stuck || self.catch_timer.is_some())
+        {
+            // in co-op the boss fires back at whoever hit the ball last
+            let player = if self.mode == GameMode::Coop && self.two_player.last_hit == PLAYER_TWO {
+                self.player_two.as_ref().unwrap()
+            } else {
+                self.player.as_ref().unwrap()
+            };
+            let target = player.position + player.size / 2.0;
+            let shots = self
+                .level_mut()
+                .boss
+                .as_mut()
+                .map(|boss| boss.attack(dt, target))
+                .unwrap_or_default();
+            self.projectiles.extend(shots);
+        }
+        self.player.as_mut().unwrap().update_animation(dt);
+        self.ball.as_mut().unwrap().game_obj.update_animation(dt);
+
+        // only time spent actually playing speeds the ball up, and the arcade never did
+        if self.state == GameState::Active
+            && !self.classic()
+            && !self.ball.as_ref().unwrap().stuck
+            && self.speed_ramp.update(dt, &self.profile())
+        {
+            self.apply_ball_speed();
+        }
 
         self.do_collisions();
+        self.update_projectiles(dt);
 
         // check loss condition
         if self.ball.as_ref().unwrap().game_obj.position.y >= self.height as f32 {
             // did ball reach bottom edge?
-            self.lives -= 1;
-            // did the player lose all his lives? : game over
-            if self.lives == 0 {
-                self.reset_level();
-                self.state = GameState::Active;
+            self.lose_life();
+        }
+        // in versus, the top edge is player two's to defend
+        let ball = &self.ball.as_ref().unwrap().game_obj;
+        if versus && ball.position.y + ball.size.y <= 0.0 {
+            self.lose_versus_life(PLAYER_TWO);
+        }
+
+        match self.mode {
+            GameMode::Endless => {
+                self.update_endless(dt);
+                return;
+            }
+            GameMode::TimeAttack => {
+                self.update_time_attack(dt);
+                return;
             }
+            GameMode::Daily => {
+                if self.state == GameState::Active && self.daily_level.is_completed() {
+                    self.finish_daily(GameState::Win);
+                }
+                return;
+            }
+            GameMode::Versus => {
+                // the match goes to the higher score once the bricks are gone
+                if self.state == GameState::Active && self.versus_level.is_completed() {
+                    self.state = GameState::Win;
+                }
+                return;
+            }
+            GameMode::Standard | GameMode::Coop => {}
+        }
+
+        if self.state == GameState::Active
+            && self.classic()
+            && self.classic_screen < CLASSIC_SCREENS
+            && self.level().is_completed()
+        {
+            // a fresh wall, keeping the score, lives and ball speed
+            self.classic_screen += 1;
+            self.paddle_shrunk = false;
+            self.classic_level.load(
+                CLASSIC_LEVEL_FILE,
+                self.width,
+                self.height / 2,
+                &self.resource_manager,
+            );
             self.reset_player();
         }
 
-        if self.state == GameState::Active && self.levels[self.current_level].is_completed() {
+        if self.state == GameState::Active && self.level().is_completed() {
+            // a network game may yet be rolled back, and the records are for local play anyway
+            self.new_high_score =
+                !self.online && self.records.submit_score(self.score_category(), self.score);
             self.reset_level();
             self.reset_player();
             //self.effects.as_mut().unwrap().chaos = true;
@@ -204,79 +599,1305 @@ impl Game {
         }
     }
 
+    // pushes in new rows as the run goes on, and ends it once they reach the paddle
+    fn update_endless(&mut self, dt: f32) {
+        if self.state != GameState::Active {
+            return;
+        }
+        if !self.ball.as_ref().unwrap().stuck {
+            let cleared = self.endless_level.is_completed();
+            if let Some(rows) = self.endless.update(dt, cleared) {
+                self.endless_level.push_rows(rows, &self.resource_manager);
+            }
+        }
+        if self.endless_level.bricks_bottom() >= self.height as f32 - PADDLE_ZONE_HEIGHT {
+            self.end_run();
+        }
+    }
+
+    // runs the level clock, and moves on to the next level once this one is done
+    fn update_time_attack(&mut self, dt: f32) {
+        if self.state != GameState::Active {
+            return;
+        }
+        self.time_attack.update(dt);
+        if !self.level().is_completed() {
+            return;
+        }
+
+        let level = level_name(LEVEL_FILES[self.current_level]);
+        let best = self.records.best_time(&level);
+        self.records
+            .submit_time(&level, self.time_attack.level_time);
+        self.time_attack.finish_level(&level, best);
+
+        self.current_level += 1;
+        if self.current_level < LEVEL_FILES.len() {
+            self.reset_level();
+            self.reset_player();
+        } else {
+            self.new_high_score = self
+                .records
+                .submit_time(CAMPAIGN_RECORD, self.time_attack.total());
+            self.current_level = 0;
+            self.reset_level();
+            self.reset_player();
+            self.state = GameState::Win;
+        }
+    }
+
+    /// Puts the current time attack level back to its start, clock and all.
+    pub fn restart_level(&mut self) {
+        if self.mode != GameMode::TimeAttack {
+            return;
+        }
+        self.reset_level();
+        self.reset_player();
+        self.time_attack.restart_level();
+    }
+
+    // records this update's input into the attempt's replay, or while watching one, takes the
+    // input from it; returns how long the update is
+    fn replay_frame(&mut self, dt: f32) -> f32 {
+        let Some(playback) = &self.playback else {
+            self.replay.frames.push(ReplayFrame {
+                dt,
+                axis: self.paddle_axis,
+                target_x: self.paddle_target_x,
+                launch: std::mem::take(&mut self.launched),
+            });
+            return dt;
+        };
+        let Some(&frame) = playback.frames.get(self.playback_frame) else {
+            // cut short, there is nothing more to watch
+            self.finish_daily(GameState::GameOver);
+            return 0.0;
+        };
+        self.playback_frame += 1;
+        self.paddle_axis = frame.axis;
+        self.paddle_target_x = frame.target_x;
+        if frame.launch {
+            self.release_ball();
+        }
+        frame.dt
+    }
+
+    // ends a daily challenge attempt, keeping the score and saving the replay if it was the day's
+    // scored one
+    fn finish_daily(&mut self, state: GameState) {
+        self.projectiles.clear();
+        self.state = state;
+        self.playback = None;
+        if !self.daily_scored {
+            return;
+        }
+        self.daily_scored = false;
+        let date = self.daily.date.to_string();
+        self.records.submit_daily(&date, self.score);
+        self.replay.score = self.score;
+        let path = Replay::path(self.daily.date);
+        self.replay.save(&path);
+        println!("Daily challenge replay saved to {}", path);
+    }
+
+    /// Watches a recorded daily challenge attempt, under the settings it was played with for the
+    /// rest of the session.
+    pub fn play_replay(&mut self, replay: Replay) {
+        replay.apply(&mut self.settings);
+        self.mode = GameMode::Daily;
+        self.playback = Some(replay);
+        self.start();
+    }
+
+    /// Hosts a network game of `mode`, versus or co-op, played under this side's settings. It
+    /// starts once the other side joins.
+    pub fn host_online(&mut self, transport: Box<dyn Transport>, mode: GameMode) {
+        self.mode = mode;
+        self.online = true;
+        let setup = self.net_setup().into_bytes();
+        self.net = Some(NetSession::host(transport, Rng::seed_from_time(), setup));
+    }
+
+    /// Joins a network game as player two, in whatever mode and settings the host picked.
+    pub fn join_online(&mut self, transport: Box<dyn Transport>) {
+        self.online = true;
+        self.net = Some(NetSession::join(transport));
+    }
+
+    /// Whether this is one side of a network game, which the menu waits on.
+    pub fn is_online(&self) -> bool {
+        self.online
+    }
+
+    // trades input with the other side of a network game, steps the game at its tick and starts
+    // and ends it
+    fn update_net(&mut self, dt: f32) {
+        let Some(mut net) = self.net.take() else {
+            return;
+        };
+        net.poll();
+        match net.status() {
+            NetStatus::Connecting => {
+                self.net = Some(net);
+                return;
+            }
+            NetStatus::Disconnected => {
+                println!("Lost the connection to the other player");
+                self.leave_online();
+                return;
+            }
+            NetStatus::Running => {}
+        }
+        if self.state == GameState::Menu {
+            // back on the menu after the match, or just connected
+            if net.frame() > 0 {
+                self.leave_online();
+                return;
+            }
+            let (seed, setup) = net.setup();
+            if let Err(err) = self.apply_net_setup(seed, setup) {
+                println!("Could not start the network game: {}", err);
+                self.leave_online();
+                return;
+            }
+            self.start();
+        }
+
+        // only the keys and stick go over the network, not the mouse
+        self.paddle_target_x = None;
+        let input = TickInput::new(self.paddle_axis, std::mem::take(&mut self.launched));
+        net.update(self, dt, input);
+        self.net = Some(net);
+    }
+
+    // back to playing on this machine alone
+    fn leave_online(&mut self) {
+        self.net = None;
+        self.online = false;
+        if matches!(self.state, GameState::Active | GameState::Paused) {
+            self.state = GameState::Menu;
+        }
+    }
+
+    // how the host has the game set up, for the other side to play the same: the mode, the
+    // level, and the settings that steer the ball and paddle
+    fn net_setup(&self) -> String {
+        format!(
+            "{} {} {} {} {} {}",
+            self.score_category(),
+            self.current_level,
+            self.settings.paddle_speed,
+            self.settings.curve_ball,
+            self.settings.min_bounce_angle,
+            self.settings.max_bounce_angle
+        )
+    }
+
+    // plays the host's setup, for the rest of the session on the joining side
+    fn apply_net_setup(&mut self, seed: u64, setup: &[u8]) -> Result<(), String> {
+        let setup = std::str::from_utf8(setup).map_err(|_| "unreadable setup")?;
+        let error = || format!("bad setup '{}'", setup);
+        let parts = setup.split_whitespace().collect::<Vec<_>>();
+        let [mode, level, paddle_speed, curve_ball, min_angle, max_angle] = parts[..] else {
+            return Err(error());
+        };
+        self.mode = match mode {
+            "versus" => GameMode::Versus,
+            "coop" => GameMode::Coop,
+            _ => return Err(error()),
+        };
+        let level = level.parse::<usize>().map_err(|_| error())?;
+        if level >= self.levels.len() {
+            return Err(error());
+        }
+        self.current_level = level;
+        self.settings.paddle_speed = paddle_speed.parse().map_err(|_| error())?;
+        self.settings.curve_ball = curve_ball.parse().map_err(|_| error())?;
+        self.settings.min_bounce_angle = min_angle.parse().map_err(|_| error())?;
+        self.settings.max_bounce_angle = max_angle.parse().map_err(|_| error())?;
+        self.net_seed = seed;
+        Ok(())
+    }
+
+    // a seed for generated levels: the match's in a network game, so both sides get the same
+    fn new_seed(&self) -> u64 {
+        if self.online {
+            self.net_seed
+        } else {
+            Rng::seed_from_time()
+        }
+    }
+
+    // the daily challenge modifiers in play, none outside of it
+    fn modifiers(&self) -> Modifiers {
+        if self.mode == GameMode::Daily {
+            self.daily.modifiers
+        } else {
+            Modifiers::default()
+        }
+    }
+
+    fn end_run(&mut self) {
+        self.new_high_score = self.records.submit_score(self.score_category(), self.score);
+        self.projectiles.clear();
+        self.state = GameState::GameOver;
+    }
+
+    // which high score the current game counts towards
+    fn score_category(&self) -> &'static str {
+        match self.mode {
+            GameMode::Endless => "endless",
+            GameMode::Standard if self.classic() => "classic",
+            GameMode::Standard => "modern",
+            // time attack keeps times instead, and the daily challenge a score per day
+            GameMode::TimeAttack => "time_attack",
+            GameMode::Daily => "daily",
+            // versus is between the two players, not against the records
+            GameMode::Versus => "versus",
+            GameMode::Coop => "coop",
+        }
+    }
+
     pub fn render(&self) {
-        if self.state == GameState::Active {
-            self.renderer.as_ref().unwrap().draw_sprite(
-                &self.resource_manager.get_texture("background"),
-                &glm::vec2(0.0, 0.0),
-                &glm::vec2(self.width as _, self.height as _),
-                &glm::vec3(1.0, 1.0, 1.0),
+        let renderer = self.renderer.as_ref().unwrap();
+        let text = self.text.as_ref().unwrap();
+        renderer.begin();
+
+        renderer.draw_sprite(
+            &self.resource_manager.get_texture("background"),
+            &glm::vec2(0.0, 0.0),
+            &glm::vec2(self.width as _, self.height as _),
+            &glm::vec3(1.0, 1.0, 1.0),
+        );
+
+        if matches!(
+            self.state,
+            GameState::Active | GameState::Paused | GameState::Menu
+        ) {
+            renderer.set_layer(1);
+            self.level().draw(renderer);
+            if self.mode == GameMode::Endless {
+                // where the bricks must not reach
+                renderer.draw(
+                    &self.resource_manager.get_texture("white"),
+                    &SpriteDesc {
+                        color: glm::vec4(1.0, 0.2, 0.2, 0.4),
+                        ..SpriteDesc::new(
+                            glm::vec2(0.0, self.height as f32 - PADDLE_ZONE_HEIGHT),
+                            glm::vec2(self.width as f32, 2.0),
+                        )
+                    },
+                );
+            }
+            renderer.set_layer(2);
+            self.player.as_ref().unwrap().draw(renderer);
+            if self.two_paddles() {
+                self.player_two.as_ref().unwrap().draw(renderer);
+            }
+            self.ball.as_ref().unwrap().draw(renderer);
+            for projectile in &self.projectiles {
+                projectile.draw(renderer);
+            }
+        }
+
+        renderer.set_layer(3);
+        let white = glm::vec4(1.0, 1.0, 1.0, 1.0);
+        let center_x = self.width as f32 / 2.0;
+        let center_y = self.height as f32 / 2.0;
+        match self.state {
+            GameState::Active if self.mode == GameMode::Versus => {
+                // each player's line by their own paddle
+                let line = |player| {
+                    format!(
+                        "{}   Score: {}   Lives: {}",
+                        player_name(player),
+                        self.two_player.scores[player],
+                        self.two_player.lives[player]
+                    )
+                };
+                let top = PLAYER_SIZE.y + 5.0;
+                let bottom = self.height as f32 - PLAYER_SIZE.y - 5.0 - text.line_height(1.0);
+                text.draw_text(renderer, &line(PLAYER_TWO), glm::vec2(5.0, top), 1.0, white);
+                text.draw_text(
+                    renderer,
+                    &line(PLAYER_ONE),
+                    glm::vec2(5.0, bottom),
+                    1.0,
+                    white,
+                );
+            }
+            GameState::Active => {
+                let mut hud = format!(
+                    "Score: {}   Lives: {}   Speed: {:.0}",
+                    self.score,
+                    self.lives,
+                    self.ball_speed()
+                );
+                if self.mode == GameMode::Endless {
+                    hud += &format!(
+                        "   Wave: {}   Best: {}",
+                        self.endless.wave(),
+                        self.records.high_score(self.score_category())
+                    );
+                } else if self.mode == GameMode::Coop {
+                    hud += &format!(
+                        "   P1: {}   P2: {}",
+                        self.two_player.scores[PLAYER_ONE], self.two_player.scores[PLAYER_TWO]
+                    );
+                } else if self.mode == GameMode::Daily {
+                    hud += &format!("   Daily: {}", self.daily.date);
+                    if self.playback.is_some() {
+                        hud += " (replay)";
+                    } else if !self.daily_scored {
+                        hud += " (practice)";
+                    }
+                } else if self.mode == GameMode::TimeAttack {
+                    hud = self.time_attack_hud();
+                    self.draw_splits(renderer, text);
+                } else if self.classic() {
+                    hud += &format!("   Screen: {}/{}", self.classic_screen, CLASSIC_SCREENS);
+                } else if self.current_level == GENERATED_LEVEL {
+                    hud += &format!("   Seed: {}", self.generated_seed);
+                }
+                if let Some(boss) = self
+                    .level()
+                    .boss
+                    .as_ref()
+                    .filter(|boss| !boss.is_defeated())
+                {
+                    hud += &format!(
+                        "   Boss: {:.0}% (phase {})",
+                        boss.health_share() * 100.0,
+                        boss.phase()
+                    );
+                }
+                text.draw_text(renderer, &hud, glm::vec2(5.0, 5.0), 1.0, white);
+            }
+            GameState::Paused => {
+                // dim the frozen scene underneath, the text goes on a layer above it
+                renderer.draw(
+                    &self.resource_manager.get_texture("white"),
+                    &SpriteDesc {
+                        color: glm::vec4(0.0, 0.0, 0.0, 0.6),
+                        ..SpriteDesc::new(
+                            glm::vec2(0.0, 0.0),
+                            glm::vec2(self.width as f32, self.height as f32),
+                        )
+                    },
+                );
+                renderer.set_layer(4);
+                text.draw_text_centered(renderer, "PAUSED", center_x, center_y - 30.0, 1.5, white);
+                text.draw_text_centered(
+                    renderer,
+                    &format!(
+                        "Press {} to resume",
+                        self.settings.bindings.describe_keys(Action::Pause)
+                    ),
+                    center_x,
+                    center_y + 20.0,
+                    0.75,
+                    white,
+                );
+            }
+            GameState::Menu if self.online => {
+                text.draw_text_centered(
+                    renderer,
+                    "Waiting for the other player...",
+                    center_x,
+                    center_y,
+                    1.0,
+                    white,
+                );
+                let side = match &self.net {
+                    Some(net) if net.local_player() == PLAYER_ONE => "Hosting as player one",
+                    _ => "Joining as player two",
+                };
+                text.draw_text_centered(renderer, side, center_x, center_y + 30.0, 0.75, white);
+            }
+            GameState::Menu => {
+                let bindings = &self.settings.bindings;
+                text.draw_text_centered(
+                    renderer,
+                    &format!("Press {} to start", bindings.describe_keys(Action::Confirm)),
+                    center_x,
+                    center_y,
+                    1.0,
+                    white,
+                );
+                text.draw_text_centered(
+                    renderer,
+                    &format!(
+                        "Mode: {}   ({}/{} to change)",
+                        self.mode.label(),
+                        bindings.describe_keys(Action::MenuLeft),
+                        bindings.describe_keys(Action::MenuRight)
+                    ),
+                    center_x,
+                    center_y - 40.0,
+                    0.75,
+                    white,
+                );
+                let hint = match (self.mode, self.settings.rules) {
+                    (GameMode::Endless, _) => format!(
+                        "Survive as long as you can, press {} for options",
+                        bindings.describe_keys(Action::Options)
+                    ),
+                    (GameMode::Standard, Rules::Modern) => format!(
+                        "Press {} or {} to select level, {} for options",
+                        bindings.describe_keys(Action::MenuUp),
+                        bindings.describe_keys(Action::MenuDown),
+                        bindings.describe_keys(Action::Options)
+                    ),
+                    (GameMode::Standard, Rules::Classic) => format!(
+                        "Classic rules, press {} for options",
+                        bindings.describe_keys(Action::Options)
+                    ),
+                    (GameMode::TimeAttack, _) => format!(
+                        "Every level against the clock, {} restarts a level",
+                        bindings.describe_keys(Action::Restart)
+                    ),
+                    (GameMode::Versus | GameMode::Coop, _) => format!(
+                        "Player two: {}/{} to move, {} to serve",
+                        bindings.describe_keys(Action::Player2Left),
+                        bindings.describe_keys(Action::Player2Right),
+                        bindings.describe_keys(Action::Player2Launch)
+                    ),
+                    (GameMode::Daily, _) => {
+                        let date = self.daily.date.to_string();
+                        match self.records.daily_score(&date) {
+                            Some(score) => {
+                                format!("Played today for {} points, practice only", score)
+                            }
+                            None => format!("Today: {}", self.daily.modifiers),
+                        }
+                    }
+                };
+                text.draw_text_centered(renderer, &hint, center_x, center_y + 30.0, 0.75, white);
+            }
+            GameState::Options => {
+                text.draw_text_centered(renderer, "OPTIONS", center_x, 60.0, 1.5, white);
+                let line_height = text.line_height(1.0) * 1.2;
+                for (i, (line, selected)) in
+                    self.options_menu.lines(&self.settings).iter().enumerate()
+                {
+                    let color = if *selected {
+                        glm::vec4(1.0, 0.85, 0.2, 1.0)
+                    } else {
+                        white
+                    };
+                    text.draw_text_centered(
+                        renderer,
+                        line,
+                        center_x,
+                        130.0 + i as f32 * line_height,
+                        1.0,
+                        color,
+                    );
+                }
+                text.draw_text_centered(
+                    renderer,
+                    "Up/Down select, Left/Right change, Esc back",
+                    center_x,
+                    self.height as f32 - 40.0,
+                    0.75,
+                    white,
+                );
+            }
+            GameState::Controls => {
+                text.draw_text_centered(renderer, "CONTROLS", center_x, 40.0, 1.5, white);
+                let line_height = text.line_height(0.75) * 1.2;
+                for (i, (line, selected)) in self
+                    .controls_menu
+                    .lines(&self.settings.bindings)
+                    .iter()
+                    .enumerate()
+                {
+                    let color = if *selected {
+                        glm::vec4(1.0, 0.85, 0.2, 1.0)
+                    } else {
+                        white
+                    };
+                    text.draw_text_centered(
+                        renderer,
+                        line,
+                        center_x,
+                        100.0 + i as f32 * line_height,
+                        0.75,
+                        color,
+                    );
+                }
+                text.draw_text_centered(
+                    renderer,
+                    "Enter adds a key or button, Delete clears, Esc back",
+                    center_x,
+                    self.height as f32 - 40.0,
+                    0.75,
+                    white,
+                );
+            }
+            GameState::Win | GameState::GameOver => {
+                let score = if self.mode == GameMode::Versus {
+                    format!(
+                        "{}: {}   {}: {}",
+                        player_name(PLAYER_ONE),
+                        self.two_player.scores[PLAYER_ONE],
+                        player_name(PLAYER_TWO),
+                        self.two_player.scores[PLAYER_TWO]
+                    )
+                } else if self.mode == GameMode::Coop {
+                    format!(
+                        "Score: {}   ({}: {}   {}: {})",
+                        self.score,
+                        player_name(PLAYER_ONE),
+                        self.two_player.scores[PLAYER_ONE],
+                        player_name(PLAYER_TWO),
+                        self.two_player.scores[PLAYER_TWO]
+                    )
+                } else if self.mode == GameMode::Daily {
+                    format!(
+                        "Daily challenge {}   Score: {}   ({})",
+                        self.daily.date, self.score, self.daily.modifiers
+                    )
+                } else if self.mode == GameMode::TimeAttack {
+                    let total = self.time_attack.total();
+                    if self.new_high_score {
+                        format!("Time: {}   New best time!", format_time(total))
+                    } else {
+                        let best = self.records.best_time(CAMPAIGN_RECORD).unwrap_or(total);
+                        format!("Time: {}   Best: {}", format_time(total), format_time(best))
+                    }
+                } else if self.new_high_score {
+                    format!("Score: {}   New high score!", self.score)
+                } else {
+                    format!(
+                        "Score: {}   Best: {}",
+                        self.score,
+                        self.records.high_score(self.score_category())
+                    )
+                };
+                text.draw_text_centered(renderer, &score, center_x, center_y - 70.0, 1.0, white);
+                let (title, color) = if self.mode == GameMode::Versus {
+                    let title = match self.two_player.winner() {
+                        Some(player) => format!("{} wins!", player_name(player)),
+                        None => "It's a draw".to_string(),
+                    };
+                    (title, glm::vec4(0.0, 1.0, 0.0, 1.0))
+                } else if self.state == GameState::Win {
+                    ("You WON!!!".to_string(), glm::vec4(0.0, 1.0, 0.0, 1.0))
+                } else if self.mode == GameMode::Endless {
+                    (
+                        "The bricks got through".to_string(),
+                        glm::vec4(1.0, 0.3, 0.2, 1.0),
+                    )
+                } else {
+                    ("Out of lives".to_string(), glm::vec4(1.0, 0.3, 0.2, 1.0))
+                };
+                text.draw_text_centered(renderer, &title, center_x, center_y - 20.0, 1.5, color);
+                text.draw_text_centered(
+                    renderer,
+                    &format!(
+                        "Press {} to return to the menu",
+                        self.settings.bindings.describe_keys(Action::Confirm)
+                    ),
+                    center_x,
+                    center_y + 30.0,
+                    1.0,
+                    glm::vec4(1.0, 1.0, 0.0, 1.0),
+                );
+            }
+        }
+        if let Some(net) = &self.net
+            && self.state == GameState::Active
+        {
+            // how far this side runs ahead of the other's input, clear of the versus lines
+            let line = format!(
+                "{} online   Ahead: {} ticks   Rollbacks: {}",
+                player_name(net.local_player()),
+                net.prediction(),
+                net.rollbacks()
             );
-            self.levels[self.current_level].draw(self.renderer.as_ref().unwrap());
-            self.player
-                .as_ref()
-                .unwrap()
-                .draw(self.renderer.as_ref().unwrap());
+            let y = self.height as f32 - PLAYER_SIZE.y - 5.0 - text.line_height(0.75);
+            text.draw_text_centered(renderer, &line, self.width as f32 * 0.75, y, 0.75, white);
+        }
+        if let Some(line) = &self.stats_line {
+            // below the score lines, a versus player two's included
+            renderer.set_layer(5);
+            let y = PLAYER_SIZE.y + 10.0 + text.line_height(1.0);
+            let yellow = glm::vec4(1.0, 1.0, 0.0, 1.0);
+            text.draw_text(renderer, line, glm::vec2(5.0, y), 0.6, yellow);
+        }
+
+        renderer.flush();
+    }
+
+    fn time_attack_hud(&self) -> String {
+        let level = level_name(LEVEL_FILES[self.current_level]);
+        let mut hud = format!(
+            "Level: {}/{}   Time: {}",
+            self.current_level + 1,
+            LEVEL_FILES.len(),
+            format_time(self.time_attack.level_time)
+        );
+        if let Some(best) = self.records.best_time(&level) {
+            hud += &format!(
+                "   Best: {} ({})",
+                format_time(best),
+                format_delta(self.time_attack.level_time - best)
+            );
+        }
+        hud
+    }
 
-            self.ball
-                .as_ref()
-                .unwrap()
-                .draw(self.renderer.as_ref().unwrap());
+    // finished levels down the right side, green where they beat the best time and red where not
+    fn draw_splits(&self, renderer: &SpriteRenderer, text: &TextRenderer) {
+        let line_height = text.line_height(0.75) * 1.2;
+        let x = self.width as f32 - 260.0;
+        let mut y = 40.0;
+        for split in &self.time_attack.splits {
+            let (delta, color) = match split.delta() {
+                Some(delta) if delta < 0.0 => (format_delta(delta), glm::vec4(0.3, 1.0, 0.3, 1.0)),
+                Some(delta) => (format_delta(delta), glm::vec4(1.0, 0.3, 0.3, 1.0)),
+                None => ("new".to_string(), glm::vec4(1.0, 1.0, 1.0, 1.0)),
+            };
+            let line = format!("{} {} {}", split.level, format_time(split.time), delta);
+            text.draw_text(renderer, &line, glm::vec2(x, y), 0.75, color);
+            y += line_height;
         }
     }
 
+    /// Streams the game to whoever connects to `spectators`, from the next update on.
+    pub fn stream_to(&mut self, spectators: SpectatorServer) {
+        self.spectators = Some(spectators);
+    }
+
+    // what watchers are sent of this update
+    fn snapshot(&self) -> Snapshot {
+        let state = match self.state {
+            GameState::Active => "active",
+            GameState::Paused => "paused",
+            GameState::Menu => "menu",
+            GameState::Options => "options",
+            GameState::Controls => "controls",
+            GameState::Win => "win",
+            GameState::GameOver => "game_over",
+        };
+        let mode = match self.mode {
+            GameMode::Standard => "standard",
+            GameMode::Endless => "endless",
+            GameMode::TimeAttack => "time_attack",
+            GameMode::Daily => "daily",
+            GameMode::Versus => "versus",
+            GameMode::Coop => "coop",
+        };
+        let mut paddles = vec![self.player.as_deref().unwrap()];
+        if self.two_paddles() {
+            paddles.push(self.player_two.as_deref().unwrap());
+        }
+        let ball = self.ball.as_deref().unwrap();
+        Snapshot {
+            state,
+            mode,
+            score: self.score,
+            lives: self.lives,
+            player_scores: self.two_paddles().then_some(self.two_player.scores),
+            paddles: paddles
+                .into_iter()
+                .map(|paddle| (paddle.position, paddle.size))
+                .collect(),
+            balls: vec![BallSnapshot {
+                position: ball.game_obj.position,
+                velocity: ball.game_obj.velocity,
+                radius: ball.radius,
+                stuck: ball.stuck,
+            }],
+            bricks: self
+                .level()
+                .bricks
+                .iter()
+                .filter(|brick| !brick.destroyed)
+                .map(|brick| BrickSnapshot {
+                    position: brick.position,
+                    size: brick.size,
+                    solid: brick.is_solid,
+                    points: brick.points,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn render_stats(&self) -> RenderStats {
+        self.renderer.as_ref().unwrap().stats()
+    }
+
+    /// Shows `line` of performance stats on screen, or nothing with `None`.
+    pub fn show_stats(&mut self, line: Option<String>) {
+        self.stats_line = line;
+    }
+
+    /// Starts a new game from the menu under the current rules and difficulty.
+    pub fn start(&mut self) {
+        if self.mode == GameMode::TimeAttack {
+            self.current_level = 0;
+            self.time_attack = TimeAttack::new();
+        }
+        if self.mode == GameMode::Daily {
+            // the day may have changed since the menu came up
+            let date = match &self.playback {
+                Some(replay) => replay.date,
+                None => Date::today(),
+            };
+            self.daily = DailyChallenge::for_date(date);
+            self.daily_scored =
+                self.playback.is_none() && self.records.daily_score(&date.to_string()).is_none();
+            // the day is used up as soon as the scored attempt starts, quitting a bad run doesn't
+            // give another go; finishing it records the real score
+            if self.daily_scored {
+                self.records.submit_daily(&date.to_string(), 0);
+            }
+            self.replay = Replay::new(date, &self.settings);
+            self.playback_frame = 0;
+            self.launched = false;
+            self.capsule_count = 0;
+            self.next_capsule = PaddleMode::Laser;
+        }
+        self.reset_level();
+        self.reset_player();
+        self.score = 0;
+        // each co-op player's share of the score starts over with the game
+        if self.mode == GameMode::Coop {
+            self.two_player = TwoPlayer::new(0);
+        }
+        self.state = GameState::Active;
+    }
+
     pub fn reset_level(&mut self) {
-        println!("current_level: {}", self.current_level);
-        match self.current_level {
-            0 => {
-                self.levels[0].load(
-                    "resources/levels/one.lvl",
+        match (self.mode, self.settings.rules) {
+            (GameMode::Endless, _) => {
+                self.endless = EndlessRun::new(Rng::seed_from_time());
+                let tiles = self.endless.first_rows();
+                self.endless_level.load_tiles(
+                    tiles,
                     self.width,
                     self.height / 2,
                     &self.resource_manager,
                 );
             }
-            1 => {
-                self.levels[1].load(
-                    "resources/levels/two.lvl",
+            (GameMode::Standard, Rules::Modern) | (GameMode::Coop, _)
+                if self.current_level == GENERATED_LEVEL =>
+            {
+                self.generate_level();
+            }
+            (GameMode::Standard, Rules::Modern)
+            | (GameMode::TimeAttack, _)
+            | (GameMode::Coop, _) => {
+                println!("current_level: {}", self.current_level);
+                self.levels[self.current_level].load(
+                    LEVEL_FILES[self.current_level],
                     self.width,
                     self.height / 2,
                     &self.resource_manager,
                 );
             }
-            2 => {
-                self.levels[2].load(
-                    "resources/levels/three.lvl",
+            (GameMode::Daily, _) => {
+                self.daily_level.load_tiles(
+                    self.daily.tiles.clone(),
                     self.width,
                     self.height / 2,
                     &self.resource_manager,
                 );
             }
-            3 => {
-                self.levels[3].load(
-                    "resources/levels/four.lvl",
+            (GameMode::Versus, _) => {
+                let field_height = self.height as f32 * VERSUS_FIELD_SHARE;
+                self.versus_level.load_tiles(
+                    field_tiles(self.new_seed()),
+                    self.width,
+                    field_height as u32,
+                    &self.resource_manager,
+                );
+                self.versus_level
+                    .move_down((self.height as f32 - field_height) / 2.0);
+                self.two_player = TwoPlayer::new(self.profile().lives);
+            }
+            (GameMode::Standard, Rules::Classic) => {
+                self.classic_level.load(
+                    CLASSIC_LEVEL_FILE,
                     self.width,
                     self.height / 2,
                     &self.resource_manager,
                 );
             }
-            _ => {}
         }
 
-        self.lives = 3;
+        self.classic_screen = 1;
+        self.paddle_shrunk = false;
+        self.lives = self.profile().lives;
+        self.speed_ramp.reset();
+    }
+
+    // lays out the generated level afresh from a new seed
+    fn generate_level(&mut self) {
+        self.generated_seed = self.new_seed();
+        let tiles = generate(&GeneratorConfig::default(), self.generated_seed);
+        // level_gen --seed writes a good one out to keep
+        println!("Generated level with seed {}", self.generated_seed);
+        self.levels[GENERATED_LEVEL].load_tiles(
+            tiles,
+            self.width,
+            self.height / 2,
+            &self.resource_manager,
+        );
+    }
+
+    // the level being played: the endless one, the selected one, or the arcade wall under
+    // classic rules
+    fn level(&self) -> &GameLevel {
+        match (self.mode, self.settings.rules) {
+            (GameMode::Endless, _) => &self.endless_level,
+            (GameMode::Daily, _) => &self.daily_level,
+            (GameMode::Versus, _) => &self.versus_level,
+            (GameMode::Standard, Rules::Modern)
+            | (GameMode::TimeAttack, _)
+            | (GameMode::Coop, _) => &self.levels[self.current_level],
+            (GameMode::Standard, Rules::Classic) => &self.classic_level,
+        }
+    }
+
+    fn level_mut(&mut self) -> &mut GameLevel {
+        match (self.mode, self.settings.rules) {
+            (GameMode::Endless, _) => &mut self.endless_level,
+            (GameMode::Daily, _) => &mut self.daily_level,
+            (GameMode::Versus, _) => &mut self.versus_level,
+            (GameMode::Standard, Rules::Modern)
+            | (GameMode::TimeAttack, _)
+            | (GameMode::Coop, _) => &mut self.levels[self.current_level],
+            (GameMode::Standard, Rules::Classic) => &mut self.classic_level,
+        }
+    }
+
+    // whether the arcade's rules apply, which they only do to the standard game
+    fn classic(&self) -> bool {
+        self.mode == GameMode::Standard && self.settings.rules == Rules::Classic
+    }
+
+    /// Steps through the game modes on the menu, showing the new mode's level behind it.
+    pub fn cycle_mode(&mut self, step: i32) {
+        let count = GameMode::ALL.len() as i32;
+        let index = GameMode::ALL
+            .iter()
+            .position(|&mode| mode == self.mode)
+            .unwrap() as i32;
+        self.mode = GameMode::ALL[(index + step).rem_euclid(count) as usize];
+        // the campaign runs from the first level
+        if self.mode == GameMode::TimeAttack {
+            self.current_level = 0;
+        }
+        if self.mode == GameMode::Daily {
+            self.daily = DailyChallenge::for_date(Date::today());
+        }
+        self.reset_level();
+        self.reset_player();
+    }
+
+    pub fn profile(&self) -> DifficultyProfile {
+        // the daily challenge is the same for everyone, and a network game for both sides
+        if self.mode == GameMode::Daily || self.online {
+            return Difficulty::Normal.profile();
+        }
+        self.settings.difficulty.profile()
+    }
+
+    /// Current ball speed in pixels per second.
+    pub fn ball_speed(&self) -> f32 {
+        let speed = self.speed_ramp.speed(&self.profile());
+        if self.modifiers().fast_ball {
+            speed * FAST_BALL_SPEEDUP
+        } else {
+            speed
+        }
+    }
+
+    // brings a ball in flight up to the current speed, keeping its direction
+    fn apply_ball_speed(&mut self) {
+        let speed = self.ball_speed();
+        let ball = self.ball.as_mut().unwrap();
+        if !ball.stuck && glm::length(&ball.game_obj.velocity) > 0.0 {
+            ball.game_obj.velocity = glm::normalize(&ball.game_obj.velocity) * speed;
+        }
+    }
+
+    fn player_size(&self) -> glm::TVec2<f32> {
+        let mut width = PLAYER_SIZE.x * self.profile().paddle_width * self.paddle_scale;
+        if self.paddle_shrunk {
+            width /= 2.0;
+        }
+        if self.modifiers().tiny_paddle {
+            width *= TINY_PADDLE_SCALE;
+        }
+        glm::vec2(width, PLAYER_SIZE.y)
+    }
+
+    // halves the paddle around its centre for the rest of the screen
+    fn shrink_player(&mut self) {
+        self.paddle_shrunk = true;
+        self.resize_player();
+    }
+
+    // brings the paddle to its current size, keeping its centre
+    fn resize_player(&mut self) {
+        let width = self.player_size().x;
+        let player = self.player.as_mut().unwrap();
+        player.position.x += (player.size.x - width) / 2.0;
+        player.size.x = width;
+        // co-op paddles share a size, both shrink together
+        if self.mode == GameMode::Coop {
+            let player_two = self.player_two.as_mut().unwrap();
+            player_two.position.x += (player_two.size.x - width) / 2.0;
+            player_two.size.x = width;
+        }
+    }
+
+    /// Freezes a game in progress, e.g. when the window loses focus.
+    pub fn pause(&mut self) {
+        // the other side of a network game plays on regardless
+        if self.state == GameState::Active && !self.online {
+            self.state = GameState::Paused;
+        }
+    }
+
+    /// Sets how hard the paddle is pushed, -1.0 (full speed left) to 1.0 (full speed right). The
+    /// paddle accelerates towards that speed rather than reaching it at once.
+    pub fn steer_player(&mut self, axis: f32) {
+        let axis = axis.clamp(-1.0, 1.0);
+        self.paddle_axis = if self.modifiers().mirrored_controls {
+            -axis
+        } else {
+            axis
+        };
+    }
+
+    /// Puts the paddle's centre at `center_x` on the next update, as a mouse does.
+    pub fn move_player_to(&mut self, center_x: f32) {
+        self.paddle_target_x = Some(self.mirror_x(center_x));
+    }
+
+    /// Where the paddle's centre is, as far as the mouse is concerned.
+    pub fn player_center_x(&self) -> f32 {
+        let player = self.player.as_ref().unwrap();
+        let center_x = self
+            .paddle_target_x
+            .unwrap_or(player.position.x + player.size.x / 2.0);
+        self.mirror_x(center_x)
+    }
+
+    // flips `x` across the screen under mirrored controls, so the mouse is mirrored too
+    fn mirror_x(&self, x: f32) -> f32 {
+        if self.modifiers().mirrored_controls {
+            self.width as f32 - x
+        } else {
+            x
+        }
+    }
+
+    // moves the paddle by its velocity, keeping it on screen and carrying a stuck ball along
+    fn update_player(&mut self, dt: f32) {
+        let player = self.player.as_mut().unwrap();
+        let old_x = player.position.x;
+        let (mut min_x, mut max_x) = (0.0, self.width as f32 - player.size.x);
+        // in co-op the mouse can't carry the paddle through player two's, it stops against it
+        if self.mode == GameMode::Coop {
+            let two = self.player_two.as_ref().unwrap();
+            if player.position.x + player.size.x / 2.0 <= two.position.x + two.size.x / 2.0 {
+                max_x = max_x.min(two.position.x - player.size.x).max(min_x);
+            } else {
+                min_x = (two.position.x + two.size.x).min(max_x);
+            }
+        }
+
+        if let Some(center_x) = self.paddle_target_x.take() {
+            // the mouse places the paddle directly, its velocity is whatever that motion was
+            player.position.x = (center_x - player.size.x / 2.0).clamp(min_x, max_x);
+            player.velocity.x = if dt > 0.0 {
+                (player.position.x - old_x) / dt
+            } else {
+                0.0
+            };
+        } else {
+            steer_paddle(
+                player,
+                self.paddle_axis,
+                self.settings.paddle_speed,
+                self.width,
+                dt,
+            );
+        }
+
+        let moved = player.position.x - old_x;
+        let serving = self.serving_player();
+        let ball = self.ball.as_mut().unwrap();
+        if ball.stuck && serving == PLAYER_ONE {
+            ball.game_obj.position.x += moved;
+        }
+    }
+
+    // moves player two's paddle like player one's, from the keys alone
+    fn update_player_two(&mut self, dt: f32) {
+        let player_two = self.player_two.as_mut().unwrap();
+        let old_x = player_two.position.x;
+        steer_paddle(
+            player_two,
+            self.player_two_axis,
+            self.settings.paddle_speed,
+            self.width,
+            dt,
+        );
+        player_two.update_animation(dt);
+
+        let moved = player_two.position.x - old_x;
+        let serving = self.serving_player();
+        let ball = self.ball.as_mut().unwrap();
+        if ball.stuck && serving == PLAYER_TWO {
+            ball.game_obj.position.x += moved;
+        }
+    }
+
+    /// Sets how hard player two pushes their paddle, like [`steer_player`](Self::steer_player).
+    pub fn steer_player_two(&mut self, axis: f32) {
+        self.player_two_axis = axis.clamp(-1.0, 1.0);
+    }
+
+    // whether player two has a paddle in this game
+    fn two_paddles(&self) -> bool {
+        matches!(self.mode, GameMode::Versus | GameMode::Coop)
+    }
+
+    // whose paddle a stuck ball waits on: player two's only when they serve in versus or co-op
+    fn serving_player(&self) -> usize {
+        if self.two_paddles() {
+            self.two_player.server
+        } else {
+            PLAYER_ONE
+        }
+    }
+
+    /// Releases the ball if it is waiting on the paddle, or fires the laser in laser mode.
+    pub fn launch_ball(&mut self) {
+        // a network game launches on its next tick, for whichever player this side is
+        if self.online {
+            self.launched = true;
+            return;
+        }
+        // a replay launches the ball itself, and player two serves their own
+        if self.playback.is_some() || self.serving_player() != PLAYER_ONE {
+            return;
+        }
+        self.launched = true;
+        self.release_ball();
+    }
+
+    fn release_ball(&mut self) {
+        let ball = self.ball.as_ref().unwrap();
+        if ball.stuck && self.mode == GameMode::TimeAttack {
+            self.time_attack.launch();
+        }
+        if ball.stuck {
+            // a caught ball leaves the way it would have bounced
+            let velocity = if self.catch_timer.take().is_some() {
+                ball.game_obj.velocity
+            } else {
+                self.initial_ball_velocity()
+            };
+            self.ball.as_mut().unwrap().launch(velocity);
+        } else if self.paddle_mode == PaddleMode::Laser && self.laser_cooldown <= 0.0 {
+            self.laser_cooldown = LASER_COOLDOWN;
+            let player = self.player.as_ref().unwrap();
+            let size = glm::vec2(LASER_SIZE.0, LASER_SIZE.1);
+            // one from each end of the paddle
+            for x in [
+                player.position.x + size.x,
+                player.position.x + player.size.x - size.x,
+            ] {
+                self.projectiles.push(Projectile::new(
+                    glm::vec2(x, player.position.y - size.y / 2.0),
+                    size,
+                    glm::vec2(0.0, -LASER_SPEED),
+                    ProjectileKind::Laser,
+                    self.resource_manager.get_texture("white"),
+                ));
+            }
+        }
+    }
+
+    // counts down the laser and lets a caught ball go once it has been held long enough
+    fn update_paddle_mode(&mut self, dt: f32) {
+        self.laser_cooldown = (self.laser_cooldown - dt).max(0.0);
+        if let Some(timer) = &mut self.catch_timer {
+            *timer -= dt;
+            if *timer <= 0.0 {
+                self.release_ball();
+            }
+        }
+    }
+
+    fn set_paddle_mode(&mut self, mode: PaddleMode) {
+        self.paddle_mode = mode;
+        self.player.as_mut().unwrap().color = mode.color();
+        // a ball held by catch mode must not be held forever
+        if mode != PaddleMode::Catch && self.catch_timer.is_some() {
+            self.release_ball();
+        }
+    }
+
+    /// Serves the ball from player two's paddle, when it is theirs to serve in versus or co-op.
+    pub fn launch_player_two_ball(&mut self) {
+        // each side of a network game plays with player one's keys
+        if !self.online {
+            self.release_player_two_ball();
+        }
+    }
+
+    fn release_player_two_ball(&mut self) {
+        if self.serving_player() != PLAYER_TWO || !self.ball.as_ref().unwrap().stuck {
+            return;
+        }
+        let mut velocity = self.initial_ball_velocity();
+        // down from the top paddle in versus
+        if self.mode == GameMode::Versus {
+            velocity.y = -velocity.y;
+        }
+        self.ball.as_mut().unwrap().launch(velocity);
+    }
+
+    /// Launch velocity of the ball at the current speed.
+    pub fn initial_ball_velocity(&self) -> glm::TVec2<f32> {
+        *LAUNCH_DIRECTION * self.ball_speed()
+    }
+
+    // takes a life, starting the game over once they are all gone
+    fn lose_life(&mut self) {
+        if self.mode == GameMode::Versus {
+            self.lose_versus_life(PLAYER_ONE);
+            return;
+        }
+        // the clock is the only penalty against it
+        if self.mode == GameMode::TimeAttack {
+            self.reset_player();
+            return;
+        }
+        self.lives -= 1;
+        // a lost ball in co-op is on both of them, they take turns serving
+        if self.mode == GameMode::Coop {
+            self.two_player.pass_serve();
+        }
+        if self.lives == 0 && self.mode == GameMode::Daily {
+            self.finish_daily(GameState::GameOver);
+            self.reset_player();
+            return;
+        }
+        if self.lives == 0 && self.mode == GameMode::Endless {
+            self.end_run();
+            self.reset_player();
+            return;
+        }
+        // did the player lose all his lives? : game over
+        if self.lives == 0 {
+            if !self.online {
+                self.records.submit_score(self.score_category(), self.score);
+            }
+            self.reset_level();
+            self.score = 0;
+            if self.mode == GameMode::Coop {
+                self.two_player = TwoPlayer::new(0);
+            }
+            self.state = GameState::Active;
+        }
+        self.reset_player();
+    }
+
+    // takes a life from the player whose edge the ball got past, ending the match once either
+    // of them is out
+    fn lose_versus_life(&mut self, player: usize) {
+        self.two_player.lose_life(player);
+        if self.two_player.is_over() {
+            self.state = GameState::Win;
+        }
+        self.reset_player();
+    }
+
+    // moves the projectiles in flight and lets those that reach the paddle take effect
+    fn update_projectiles(&mut self, dt: f32) {
+        let mut hits = Vec::new();
+        let player = self.player.as_ref().unwrap();
+        // in co-op the shots can come down on either paddle
+        let player_two = (self.mode == GameMode::Coop).then(|| self.player_two.as_deref().unwrap());
+        let (width, height) = (self.width, self.height);
+        self.projectiles.retain_mut(|projectile| {
+            projectile.update(dt);
+            // the paddle's own lasers fly up and away from it
+            if projectile.kind != ProjectileKind::Laser {
+                let hit = if projectile.overlaps(player) {
+                    Some(PLAYER_ONE)
+                } else if player_two.is_some_and(|two| projectile.overlaps(two)) {
+                    Some(PLAYER_TWO)
+                } else {
+                    None
+                };
+                if let Some(hit) = hit {
+                    hits.push((projectile.kind, hit));
+                    return false;
+                }
+            }
+            !projectile.is_off_screen(width, height)
+        });
+
+        for (kind, hit) in hits {
+            match kind {
+                ProjectileKind::Shrink => {
+                    self.paddle_scale =
+                        (self.paddle_scale * PROJECTILE_SHRINK).max(MIN_PADDLE_SCALE);
+                    self.resize_player();
+                    let paddle = if hit == PLAYER_TWO {
+                        self.player_two.as_mut().unwrap()
+                    } else {
+                        self.player.as_mut().unwrap()
+                    };
+                    paddle.animation = Some(paddle_flash());
+                }
+                ProjectileKind::Lethal => {
+                    self.lose_life();
+                    // the rest of the volley went with the life
+                    break;
+                }
+                ProjectileKind::Capsule(mode) => self.set_paddle_mode(mode),
+                ProjectileKind::Laser => {}
+            }
+        }
     }
 
     pub fn reset_player(&mut self) {
         // reset player/ball stats
-        self.player.as_mut().unwrap().size = *PLAYER_SIZE;
+        self.paddle_scale = 1.0;
+        self.projectiles.clear();
+        self.paddle_mode = PaddleMode::Normal;
+        self.laser_cooldown = 0.0;
+        self.catch_timer = None;
+        let player_size = self.player_size();
+        // co-op players start a third of the way in from either side
+        let (player_x, player_two_x) = if self.mode == GameMode::Coop {
+            (self.width as f32 / 3.0, self.width as f32 * 2.0 / 3.0)
+        } else {
+            (self.width as f32 / 2.0, self.width as f32 / 2.0)
+        };
+        self.player.as_mut().unwrap().size = player_size;
+        self.player.as_mut().unwrap().velocity = glm::vec2(0.0, 0.0);
         self.player.as_mut().unwrap().position = glm::vec2(
-            self.width as f32 / 2.0 - PLAYER_SIZE.x / 2.0,
-            self.height as f32 - PLAYER_SIZE.y,
+            player_x - player_size.x / 2.0,
+            self.height as f32 - player_size.y,
         );
+        let velocity = self.initial_ball_velocity();
         self.ball.as_mut().unwrap().reset(
             self.player.as_ref().unwrap().position
-                + glm::vec2(PLAYER_SIZE.x / 2.0 - BALL_RADIUS, -(BALL_RADIUS * 2.0)),
-            *INITIAL_BALL_VELOCITY,
+                + glm::vec2(player_size.x / 2.0 - BALL_RADIUS, -(BALL_RADIUS * 2.0)),
+            velocity,
         );
         // also disable all active powerups
         //self.effects.as_mut().unwrap().chaos = false;
@@ -285,40 +1906,173 @@ impl Game {
         //self.ball.as_mut().unwrap().sticky = false;
         self.player.as_mut().unwrap().color = glm::vec3(1.0, 1.0, 1.0);
         self.ball.as_mut().unwrap().game_obj.color = glm::vec3(1.0, 1.0, 1.0);
+
+        let serving = self.serving_player();
+        let versus = self.mode == GameMode::Versus;
+        let player_two = self.player_two.as_mut().unwrap();
+        player_two.size = player_size;
+        player_two.velocity = glm::vec2(0.0, 0.0);
+        let player_two_y = if versus {
+            0.0
+        } else {
+            self.height as f32 - player_size.y
+        };
+        player_two.position = glm::vec2(player_two_x - player_size.x / 2.0, player_two_y);
+        if serving == PLAYER_TWO {
+            // in versus, down from under the top paddle
+            let (offset_y, velocity) = if versus {
+                (player_size.y, glm::vec2(velocity.x, -velocity.y))
+            } else {
+                (-(BALL_RADIUS * 2.0), velocity)
+            };
+            let position =
+                player_two.position + glm::vec2(player_size.x / 2.0 - BALL_RADIUS, offset_y);
+            self.ball.as_mut().unwrap().reset(position, velocity);
+        }
     }
 
     fn do_collisions(&mut self) {
-        for box_obj in self.levels[self.current_level].bricks.iter_mut() {
+        let classic = self.classic();
+        let versus = self.mode == GameMode::Versus;
+        let two_paddles = self.two_paddles();
+        // the arcade never had capsules, and with two players they would only help player one
+        let capsules_drop = !classic && !two_paddles && !self.modifiers().no_power_ups;
+        let capsule_every = self.profile().capsule_every;
+        let top_rows_bottom = self.level().top_rows_bottom();
+        let level = match (self.mode, self.settings.rules) {
+            (GameMode::Endless, _) => &mut self.endless_level,
+            (GameMode::Daily, _) => &mut self.daily_level,
+            (GameMode::Versus, _) => &mut self.versus_level,
+            (GameMode::Standard, Rules::Modern)
+            | (GameMode::TimeAttack, _)
+            | (GameMode::Coop, _) => &mut self.levels[self.current_level],
+            (GameMode::Standard, Rules::Classic) => &mut self.classic_level,
+        };
+        let mut sped_up = false;
+        let mut boss_points = 0;
+        let mut capsules = Vec::new();
+        let mut destroy_brick = |brick: &mut GameObject| {
+            brick.destroyed = true;
+            if two_paddles {
+                self.two_player.scores[self.two_player.last_hit] += brick.points;
+            }
+            // co-op players score as a team too
+            if !versus {
+                self.score += brick.points;
+            }
+            // the arcade speeds up on the first orange or red brick
+            let in_top_rows = if classic {
+                brick.points >= 5
+            } else {
+                brick.position.y < top_rows_bottom
+            };
+            sped_up |= self.speed_ramp.brick_destroyed(in_top_rows);
+            brick.set_sprite(self.resource_manager.get_texture("block_shatter"));
+            brick.animation = Some(
+                Animation::from_strip(SHATTER_FRAMES as usize, 0.05).with_mode(PlayMode::Once),
+            );
+
+            self.capsule_count += 1;
+            if capsules_drop && self.capsule_count.is_multiple_of(capsule_every) {
+                let mode = self.next_capsule;
+                self.next_capsule = match mode {
+                    PaddleMode::Laser => PaddleMode::Catch,
+                    _ => PaddleMode::Laser,
+                };
+                capsules.push(Projectile::new(
+                    brick.position + brick.size / 2.0,
+                    glm::vec2(CAPSULE_SIZE.0, CAPSULE_SIZE.1),
+                    glm::vec2(0.0, CAPSULE_SPEED),
+                    ProjectileKind::Capsule(mode),
+                    self.resource_manager.get_texture("block"),
+                ));
+            }
+        };
+
+        for box_obj in level.bricks.iter_mut() {
             let collision = Game::check_collision(self.ball.as_ref().unwrap(), box_obj);
             if !box_obj.destroyed && collision.0 {
                 if !box_obj.is_solid {
-                    box_obj.destroyed = true;
+                    destroy_brick(box_obj);
                 }
+                bounce_ball(self.ball.as_mut().unwrap(), collision, box_obj.velocity);
+            }
+        }
 
-                let direction = collision.1;
-                let diff_vector = collision.2;
-                if direction == Direction::Left || direction == Direction::Right {
-                    // horizontal collision
-                    self.ball.as_mut().unwrap().game_obj.velocity.x =
-                        -self.ball.as_ref().unwrap().game_obj.velocity.x;
-                    // relocate
-                    let penetration = self.ball.as_ref().unwrap().radius - diff_vector.x.abs();
-                    if direction == Direction::Left {
-                        self.ball.as_mut().unwrap().game_obj.position.x += penetration; // move ball to right
-                    } else {
-                        self.ball.as_mut().unwrap().game_obj.position.x -= penetration; // move ball to left;
-                    }
+        if let Some(boss) = &mut level.boss {
+            let ball = self.ball.as_mut().unwrap();
+            for i in 0..boss.parts.len() {
+                let part = &boss.parts[i].game_obj;
+                let collision = Game::check_collision(ball, part);
+                if !part.destroyed && collision.0 {
+                    let part_velocity = part.velocity;
+                    boss_points += boss.hit(i);
+                    bounce_ball(ball, collision, part_velocity);
+                }
+            }
+        }
+
+        // lasers stop at the first brick or boss part they reach, solid or not
+        self.projectiles.retain(|projectile| {
+            if projectile.kind != ProjectileKind::Laser {
+                return true;
+            }
+            if let Some(brick) = level
+                .bricks
+                .iter_mut()
+                .find(|brick| !brick.destroyed && projectile.overlaps(brick))
+            {
+                if !brick.is_solid {
+                    destroy_brick(brick);
+                }
+                return false;
+            }
+            if let Some(boss) = &mut level.boss
+                && let Some(i) = (0..boss.parts.len()).find(|&i| {
+                    !boss.parts[i].game_obj.destroyed
+                        && projectile.overlaps(&boss.parts[i].game_obj)
+                })
+            {
+                boss_points += boss.hit(i);
+                return false;
+            }
+            true
+        });
+        self.projectiles.extend(capsules);
+        self.score += boss_points;
+
+        if sped_up {
+            self.apply_ball_speed();
+        }
+
+        // co-op paddles can't pass through each other: where they overlap both give way, as far
+        // as the walls let them, and neither keeps pushing into the other
+        if self.mode == GameMode::Coop {
+            let serving = self.two_player.server;
+            let one = self.player.as_mut().unwrap();
+            let two = self.player_two.as_mut().unwrap();
+            // either may be on the left, whichever centre is further left
+            let one_left = one.position.x + one.size.x / 2.0 <= two.position.x + two.size.x / 2.0;
+            let (left, right) = if one_left { (one, two) } else { (two, one) };
+            let overlap = left.position.x + left.size.x - right.position.x;
+            if overlap > 0.0 {
+                let left_x = (left.position.x - overlap / 2.0).max(0.0);
+                let right_x = (left_x + left.size.x).min(self.width as f32 - right.size.x);
+                let left_x = right_x - left.size.x;
+                let moved_left = left_x - left.position.x;
+                let moved_right = right_x - right.position.x;
+                left.position.x = left_x;
+                right.position.x = right_x;
+                left.velocity.x = left.velocity.x.min(0.0);
+                right.velocity.x = right.velocity.x.max(0.0);
+                let moved = if one_left {
+                    [moved_left, moved_right]
                 } else {
-                    // vertical collision
-                    self.ball.as_mut().unwrap().game_obj.velocity.y =
-                        -self.ball.as_ref().unwrap().game_obj.velocity.y; // reverse vertical velocity
-                    // relocate
-                    let penetration = self.ball.as_ref().unwrap().radius - diff_vector.y.abs();
-                    if direction == Direction::Up {
-                        self.ball.as_mut().unwrap().game_obj.position.y -= penetration; // move ball back up
-                    } else {
-                        self.ball.as_mut().unwrap().game_obj.position.y += penetration; // move ball back down
-                    }
+                    [moved_right, moved_left]
+                };
+                let ball = self.ball.as_mut().unwrap();
+                if ball.stuck {
+                    ball.game_obj.position.x += moved[serving];
                 }
             }
         }
@@ -327,30 +2081,40 @@ impl Game {
         let result =
             Game::check_collision(self.ball.as_ref().unwrap(), self.player.as_ref().unwrap());
         if !self.ball.as_ref().unwrap().stuck && result.0 {
-            // check where it hit the board, and change velocity based on where it hit the board
-            let center_board = self.player.as_ref().unwrap().position.x
-                + self.player.as_ref().unwrap().size.x / 2.0;
-            let distance = self.ball.as_ref().unwrap().game_obj.position.x
-                + self.ball.as_ref().unwrap().radius
-                - center_board;
-            let percentage = distance / (self.player.as_ref().unwrap().size.x / 2.0);
-            // then move accordingly
-            let strength = 2.0f32;
-            let old_velocity = self.ball.as_ref().unwrap().game_obj.velocity;
-            self.ball.as_mut().unwrap().game_obj.velocity.x =
-                INITIAL_BALL_VELOCITY.x * percentage * strength;
-            // self.ball.as_mut().unwrap().game_obj.velocity.y =
-            // -self.ball.as_ref().unwrap().game_obj.velocity.y;
-            self.ball.as_mut().unwrap().game_obj.velocity =
-                glm::normalize(&self.ball.as_ref().unwrap().game_obj.velocity)
-                    * glm::length(&old_velocity); // keep speed consistent over both axes (multiply by length of old velocity, so total strength is not changed)
-            // fix sticky paddle
-            self.ball.as_mut().unwrap().game_obj.velocity.y =
-                -self.ball.as_ref().unwrap().game_obj.velocity.y.abs();
-
-            // if Sticky powerup is activated, also stick ball to paddle once new velocity vectors
-            // were calculated
-            //self.ball.as_mut().unwrap().stuck = self.ball.as_ref().unwrap().sticky;
+            bounce_off_paddle(
+                self.ball.as_mut().unwrap(),
+                self.player.as_ref().unwrap(),
+                true,
+                &self.settings,
+            );
+            self.player.as_mut().unwrap().animation = Some(paddle_flash());
+            self.two_player.last_hit = PLAYER_ONE;
+
+            // catch mode holds the ball on top of the paddle, already aimed where it would go
+            if self.paddle_mode == PaddleMode::Catch {
+                let paddle_top = self.player.as_ref().unwrap().position.y;
+                let ball = self.ball.as_mut().unwrap();
+                ball.game_obj.position.y = paddle_top - ball.radius * 2.0;
+                ball.catch();
+                self.catch_timer = Some(CATCH_TIMEOUT);
+            }
+        }
+
+        if two_paddles {
+            let result = Game::check_collision(
+                self.ball.as_ref().unwrap(),
+                self.player_two.as_ref().unwrap(),
+            );
+            if !self.ball.as_ref().unwrap().stuck && result.0 {
+                bounce_off_paddle(
+                    self.ball.as_mut().unwrap(),
+                    self.player_two.as_ref().unwrap(),
+                    !versus,
+                    &self.settings,
+                );
+                self.player_two.as_mut().unwrap().animation = Some(paddle_flash());
+                self.two_player.last_hit = PLAYER_TWO;
+            }
         }
     }
 
@@ -403,6 +2167,162 @@ impl Game {
     }
 }
 
+// what a level file is called in the records and on screen, its file name without the extension
+fn level_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// briefly tints the paddle when the ball bounces off it
+fn paddle_flash() -> Animation {
+    let frames = [0.6, 1.0, 0.6, 1.0]
+        .map(|green_blue| Frame {
+            tint: glm::vec4(1.0, green_blue, green_blue, 1.0),
+            ..Frame::new([0.0, 0.0, 1.0, 1.0], 0.05)
+        })
+        .to_vec();
+    Animation::new(frames, PlayMode::Once)
+}
+
+// speeds the paddle up or down towards `axis` (-1.0 to 1.0) of full speed and moves it, keeping
+// it on screen
+fn steer_paddle(paddle: &mut GameObject, axis: f32, speed: f32, screen_width: u32, dt: f32) {
+    let old_x = paddle.position.x;
+    let max_x = screen_width as f32 - paddle.size.x;
+    let target = axis * speed;
+    let speeding_up =
+        target.abs() > paddle.velocity.x.abs() && target.signum() * paddle.velocity.x >= 0.0;
+    let rate = if speeding_up {
+        PADDLE_ACCELERATION
+    } else {
+        PADDLE_DECELERATION
+    };
+    paddle.velocity.x += (target - paddle.velocity.x).clamp(-rate * dt, rate * dt);
+    paddle.position.x = (old_x + paddle.velocity.x * dt).clamp(0.0, max_x);
+
+    // stop dead against the walls instead of pushing into them
+    if (paddle.position.x <= 0.0 && paddle.velocity.x < 0.0)
+        || (paddle.position.x >= max_x && paddle.velocity.x > 0.0)
+    {
+        paddle.velocity.x = 0.0;
+    }
+}
+
+// sends the ball off a paddle, `upwards` off the bottom one and down off a top one, at an angle
+// set by where on the paddle it landed
+fn bounce_off_paddle(
+    ball: &mut BallObject,
+    paddle: &GameObject,
+    upwards: bool,
+    settings: &Settings,
+) {
+    // check where it hit the board, and change velocity based on where it hit the board
+    let center_board = paddle.position.x + paddle.size.x / 2.0;
+    let distance = ball.game_obj.position.x + ball.radius - center_board;
+    let percentage = distance / (paddle.size.x / 2.0);
+    // then move accordingly
+    // a moving paddle drags the ball along with it (english) and sets it spinning
+    let paddle_velocity = paddle.velocity.x;
+    let old_velocity = ball.game_obj.velocity;
+    ball.game_obj.velocity.x = glm::length(&old_velocity) * PADDLE_DEFLECTION * percentage
+        + paddle_velocity * PADDLE_ENGLISH;
+    // keep speed consistent over both axes (multiply by length of old velocity, so total strength
+    // is not changed)
+    ball.game_obj.velocity = glm::normalize(&ball.game_obj.velocity) * glm::length(&old_velocity);
+    // fix sticky paddle
+    ball.game_obj.velocity.y = -ball.game_obj.velocity.y.abs();
+    // never leave so flat the ball takes forever to come back, or so steep it's boring
+    ball.game_obj.velocity = clamp_bounce_angle(
+        ball.game_obj.velocity,
+        settings.min_bounce_angle,
+        settings.max_bounce_angle,
+    );
+    if !upwards {
+        ball.game_obj.velocity.y = -ball.game_obj.velocity.y;
+    }
+    ball.spin = (paddle_velocity * PADDLE_SPIN).clamp(-MAX_SPIN, MAX_SPIN);
+}
+
+// sends the ball back off whatever it hit and out of it
+fn bounce_ball(ball: &mut BallObject, collision: Collision, obstacle_velocity: glm::TVec2<f32>) {
+    let (_, direction, diff_vector) = collision;
+    let moving = obstacle_velocity != glm::vec2(0.0, 0.0);
+    if direction == Direction::Left || direction == Direction::Right {
+        // horizontal collision
+        if moving {
+            ball.game_obj.velocity =
+                bounce_off_moving(ball.game_obj.velocity, obstacle_velocity, &direction);
+        } else {
+            ball.game_obj.velocity.x = -ball.game_obj.velocity.x;
+        }
+        // relocate
+        let penetration = ball.radius - diff_vector.x.abs();
+        if direction == Direction::Left {
+            ball.game_obj.position.x += penetration; // move ball to right
+        } else {
+            ball.game_obj.position.x -= penetration; // move ball to left;
+        }
+    } else {
+        // vertical collision
+        if moving {
+            ball.game_obj.velocity =
+                bounce_off_moving(ball.game_obj.velocity, obstacle_velocity, &direction);
+        } else {
+            ball.game_obj.velocity.y = -ball.game_obj.velocity.y; // reverse vertical velocity
+        }
+        // relocate
+        let penetration = ball.radius - diff_vector.y.abs();
+        if direction == Direction::Up {
+            ball.game_obj.position.y -= penetration; // move ball back up
+        } else {
+            ball.game_obj.position.y += penetration; // move ball back down
+        }
+    }
+}
+
+// reflects the ball off a moving brick as seen from the brick, then sends it away from the brick
+// at its own speed so the brick can neither push it faster nor trap it
+fn bounce_off_moving(
+    velocity: glm::TVec2<f32>,
+    brick_velocity: glm::TVec2<f32>,
+    direction: &Direction,
+) -> glm::TVec2<f32> {
+    let speed = glm::length(&velocity);
+    let mut relative = velocity - brick_velocity;
+    match direction {
+        Direction::Left | Direction::Right => relative.x = -relative.x,
+        Direction::Up | Direction::Down => relative.y = -relative.y,
+    }
+    let mut bounced = relative + brick_velocity;
+    match direction {
+        Direction::Left => bounced.x = bounced.x.abs(),
+        Direction::Right => bounced.x = -bounced.x.abs(),
+        Direction::Up => bounced.y = -bounced.y.abs(),
+        Direction::Down => bounced.y = bounced.y.abs(),
+    }
+    if glm::length(&bounced) > 0.0 {
+        glm::normalize(&bounced) * speed
+    } else {
+        velocity
+    }
+}
+
+// limits an upward velocity to between `min` and `max` degrees above the horizontal
+fn clamp_bounce_angle(velocity: glm::TVec2<f32>, min: f32, max: f32) -> glm::TVec2<f32> {
+    let speed = glm::length(&velocity);
+    let angle = (-velocity.y)
+        .atan2(velocity.x.abs())
+        .to_degrees()
+        .clamp(min, max)
+        .to_radians();
+    glm::vec2(
+        velocity.x.signum() * angle.cos() * speed,
+        -angle.sin() * speed,
+    )
+}
+
 fn vector_direction(target: glm::TVec2<f32>) -> Direction {
     let compass = [
         glm::vec2(0.0f32, 1.0), // up
@@ -412,8 +2332,8 @@ fn vector_direction(target: glm::TVec2<f32>) -> Direction {
     ];
     let mut max = 0.0f32;
     let mut best_match = -1isize;
-    for i in 0..4 {
-        let dot_product = glm::dot(&glm::normalize(&target), &compass[i]);
+    for (i, direction) in compass.iter().enumerate() {
+        let dot_product = glm::dot(&glm::normalize(&target), direction);
         if dot_product > max {
             max = dot_product;
             best_match = i as isize;
@@ -431,6 +2351,53 @@ fn vector_direction(target: glm::TVec2<f32>) -> Direction {
     }
 }
 
+impl Simulation for Game {
+    type State = NetState;
+
+    fn save_state(&self) -> NetState {
+        NetState {
+            state: self.state,
+            lives: self.lives,
+            score: self.score,
+            two_player: self.two_player.clone(),
+            player: self.player.as_deref().unwrap().clone(),
+            player_two: self.player_two.as_deref().unwrap().clone(),
+            ball: self.ball.as_deref().unwrap().clone(),
+            level: self.level().clone(),
+            speed_ramp: self.speed_ramp.clone(),
+            projectiles: self.projectiles.clone(),
+            paddle_scale: self.paddle_scale,
+        }
+    }
+
+    fn load_state(&mut self, state: &NetState) {
+        self.state = state.state;
+        self.lives = state.lives;
+        self.score = state.score;
+        self.two_player = state.two_player.clone();
+        *self.player.as_deref_mut().unwrap() = state.player.clone();
+        *self.player_two.as_deref_mut().unwrap() = state.player_two.clone();
+        *self.ball.as_deref_mut().unwrap() = state.ball.clone();
+        *self.level_mut() = state.level.clone();
+        self.speed_ramp = state.speed_ramp.clone();
+        self.projectiles = state.projectiles.clone();
+        self.paddle_scale = state.paddle_scale;
+    }
+
+    fn advance(&mut self, inputs: [TickInput; 2]) {
+        self.paddle_axis = inputs[PLAYER_ONE].axis();
+        self.player_two_axis = inputs[PLAYER_TWO].axis();
+        let serving = self.serving_player();
+        if inputs[serving].launch {
+            match serving {
+                PLAYER_ONE => self.release_ball(),
+                _ => self.release_player_two_ball(),
+            }
+        }
+        self.step(TICK);
+    }
+}
+
 impl Drop for Game {
     fn drop(&mut self) {
         if let Some(renderer) = self.renderer.take() {
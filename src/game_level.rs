@@ -9,34 +9,96 @@ use std::{
 use nalgebra_glm as glm;
 
 use crate::{
+    boss::{
+        Boss,
+        parse_boss_part,
+    },
+    brick_motion::{
+        BrickMotion,
+        parse_motion,
+    },
     game_object::GameObject,
     resource_manager::ResourceManager,
     sprite_renderer::SpriteRenderer,
 };
 
+// share of the brick rows, from the top, that count as the top rows
+const TOP_ROWS_SHARE: f32 = 0.25;
+
+// bricks that move together, see `BrickMotion`
+#[derive(Clone)]
+struct Formation {
+    bricks: Vec<usize>,
+    motion: BrickMotion,
+    time: f32,
+    // in tiles, where the motion had taken the bricks at the last update
+    offset: glm::TVec2<f32>,
+    // in pixels, how far the bricks have really gone, less than the motion when the floor
+    // stopped them
+    travelled: glm::TVec2<f32>,
+}
+
+#[derive(Clone)]
 pub struct GameLevel {
     pub bricks: Vec<GameObject>,
+    // bricks above this line are in the top rows
+    top_rows_bottom: f32,
+    formations: Vec<Formation>,
+    unit_size: glm::TVec2<f32>,
+    pub boss: Option<Boss>,
 }
 
 impl GameLevel {
     pub fn new() -> Self {
-        Self { bricks: Vec::new() }
+        Self {
+            bricks: Vec::new(),
+            top_rows_bottom: 0.0,
+            formations: Vec::new(),
+            unit_size: glm::vec2(0.0, 0.0),
+            boss: None,
+        }
     }
 
+    /// Loads a level file: one line of tiles per row of bricks, optionally followed by motion
+    /// lines (see [`parse_motion`]), boss lines (see [`parse_boss_part`]) and `#` comments. A level
+    /// fought against a boss alone still needs rows of `0` tiles to set the size of a tile.
     pub fn load(
         &mut self,
-        file: &str,
+        path: &str,
         level_width: u32,
         level_height: u32,
         resource_manager: &ResourceManager,
     ) {
-        let file = File::open(file).expect("Failed to open file");
+        self.bricks.clear();
+        self.formations.clear();
+        self.boss = None;
+        let file = File::open(path).expect("Failed to open file");
         let reader = BufReader::new(file);
 
         let mut tile_data = Vec::<Vec<u32>>::new();
+        let mut motions = Vec::new();
+        let mut boss_parts = Vec::new();
 
         for line in reader.lines() {
             let line = line.unwrap();
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with("boss") {
+                match parse_boss_part(line) {
+                    Ok(part) => boss_parts.push(part),
+                    Err(err) => println!("{}: skipping '{}': {}", path, line, err),
+                }
+                continue;
+            }
+            if line.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                match parse_motion(line) {
+                    Ok(motion) => motions.push(motion),
+                    Err(err) => println!("{}: skipping '{}': {}", path, line, err),
+                }
+                continue;
+            }
             let nums = line
                 .split_whitespace()
                 .map(|x| x.parse::<u32>().unwrap())
@@ -44,82 +106,197 @@ impl GameLevel {
             tile_data.push(nums);
         }
 
-        if tile_data.len() > 0 {
+        if !tile_data.is_empty() {
+            let rows = self.init(tile_data, level_width, level_height, resource_manager);
+            for (range, motion) in motions {
+                let bricks = (0..self.bricks.len())
+                    .filter(|&i| range.contains(&rows[i]))
+                    .collect();
+                self.formations.push(Formation {
+                    bricks,
+                    motion,
+                    time: 0.0,
+                    offset: glm::vec2(0.0, 0.0),
+                    travelled: glm::vec2(0.0, 0.0),
+                });
+            }
+            if !boss_parts.is_empty() {
+                let mut boss = Boss::new(self.unit_size.x, resource_manager);
+                for (position, size, health) in boss_parts {
+                    boss.add_part(
+                        position.component_mul(&self.unit_size),
+                        size.component_mul(&self.unit_size),
+                        health,
+                        resource_manager,
+                    );
+                }
+                self.boss = Some(boss);
+            }
+        }
+    }
+
+    /// Builds the level from tiles as they would appear in a level file, one `Vec` per row.
+    pub fn load_tiles(
+        &mut self,
+        tile_data: Vec<Vec<u32>>,
+        level_width: u32,
+        level_height: u32,
+        resource_manager: &ResourceManager,
+    ) {
+        self.bricks.clear();
+        self.formations.clear();
+        self.boss = None;
+        if !tile_data.is_empty() {
             self.init(tile_data, level_width, level_height, resource_manager);
         }
     }
 
+    // builds the bricks, returning the row each one is on
     fn init(
         &mut self,
         tile_data: Vec<Vec<u32>>,
         level_width: u32,
         level_height: u32,
         resource_manager: &ResourceManager,
-    ) {
+    ) -> Vec<usize> {
         let num_tiles_per_row = tile_data[0].len();
         let rows = tile_data.len();
 
         let unit_width = level_width as f32 / num_tiles_per_row as f32;
         let unit_height = level_height as f32 / rows as f32;
+        self.top_rows_bottom = unit_height * (rows as f32 * TOP_ROWS_SHARE).ceil();
+        self.unit_size = glm::vec2(unit_width, unit_height);
+        let mut brick_rows = Vec::new();
 
-        for y in 0..rows {
-            for x in 0..num_tiles_per_row {
+        for (y, row) in tile_data.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate().take(num_tiles_per_row) {
                 let pos = glm::vec2(unit_width * x as f32, unit_height * y as f32);
-                let size = glm::vec2(unit_width, unit_height);
-
-                if tile_data[y][x] == 1 {
-                    //solid
-                    let mut solid_brick = GameObject::new(
-                        pos,
-                        size,
-                        glm::vec2(0.0, 0.0),
-                        resource_manager.get_texture("block_solid"),
-                        glm::vec3(0.8, 0.8, 0.7),
-                    );
-                    solid_brick.is_solid = true;
-                    self.bricks.push(solid_brick);
-                } else if tile_data[y][x] > 1 {
-                    //non solid
-                    let mut color = glm::vec3(1.0, 1.0, 1.0); // original: white
-                    match tile_data[y][x] {
-                        2 => {
-                            color = glm::vec3(0.2, 0.6, 1.0);
-                        }
-                        3 => {
-                            color = glm::vec3(0.0, 0.7, 0.0);
-                        }
-                        4 => {
-                            color = glm::vec3(0.8, 0.8, 0.4);
-                        }
-                        5 => {
-                            color = glm::vec3(1.0, 0.5, 0.0);
-                        }
-                        _ => {}
-                    }
-                    self.bricks.push(GameObject::new(
-                        pos,
-                        size,
-                        glm::vec2(0.0, 0.0),
-                        resource_manager.get_texture("block"),
-                        color,
-                    ));
+                if let Some(brick) = make_brick(tile, pos, self.unit_size, resource_manager) {
+                    self.bricks.push(brick);
+                    brick_rows.push(y);
                 }
+            }
+        }
+        brick_rows
+    }
+
+    /// Bricks above this line are in the top rows, which speed the ball up the first time it
+    /// gets there.
+    pub fn top_rows_bottom(&self) -> f32 {
+        self.top_rows_bottom
+    }
 
-                //let brick = GameObject::new(pos, size);
-                //self.bricks.push(brick);
+    /// Pushes rows of tiles in at the top, moving every brick already in the level down to make
+    /// room. Bricks that have finished shattering are dropped, and with them any motion lines.
+    pub fn push_rows(&mut self, tile_data: Vec<Vec<u32>>, resource_manager: &ResourceManager) {
+        self.formations.clear();
+        self.bricks.retain(|brick| {
+            !brick.destroyed
+                || brick
+                    .animation
+                    .as_ref()
+                    .is_some_and(|animation| !animation.is_finished())
+        });
+        let drop = self.unit_size.y * tile_data.len() as f32;
+        for brick in self.bricks.iter_mut() {
+            brick.position.y += drop;
+        }
+        for (y, row) in tile_data.iter().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                let pos = self.unit_size.component_mul(&glm::vec2(x as f32, y as f32));
+                if let Some(brick) = make_brick(tile, pos, self.unit_size, resource_manager) {
+                    self.bricks.push(brick);
+                }
+            }
+        }
+    }
+
+    /// Moves every brick down by `distance`, leaving room above the level.
+    pub fn move_down(&mut self, distance: f32) {
+        for brick in self.bricks.iter_mut() {
+            brick.position.y += distance;
+        }
+        self.top_rows_bottom += distance;
+    }
+
+    /// Lowest edge of the bricks still standing, 0.0 if there are none.
+    pub fn bricks_bottom(&self) -> f32 {
+        self.bricks
+            .iter()
+            .filter(|brick| !brick.destroyed)
+            .map(|brick| brick.position.y + brick.size.y)
+            .fold(0.0, f32::max)
+    }
+
+    /// Moves the bricks on by `dt`, never letting a formation carry its bricks down past `floor`.
+    pub fn update(&mut self, dt: f32, floor: f32) {
+        for brick in self.bricks.iter_mut() {
+            brick.update_animation(dt);
+            brick.velocity = glm::vec2(0.0, 0.0);
+        }
+        if dt <= 0.0 {
+            return;
+        }
+        for formation in self.formations.iter_mut() {
+            formation.time += dt;
+            let offset = formation.motion.offset(formation.time);
+            let mut moved = (offset - formation.offset).component_mul(&self.unit_size);
+            formation.offset = offset;
+            // descending rows stop once their lowest brick reaches the floor
+            let bottom = formation
+                .bricks
+                .iter()
+                .map(|&i| &self.bricks[i])
+                .filter(|brick| !brick.destroyed)
+                .map(|brick| brick.position.y + brick.size.y)
+                .fold(f32::MIN, f32::max);
+            if moved.y > 0.0 {
+                moved.y = moved.y.min((floor - bottom).max(0.0));
+            }
+            formation.travelled += moved;
+            for &i in &formation.bricks {
+                // velocity adds up for a brick in more than one formation
+                self.bricks[i].position += moved;
+                self.bricks[i].velocity += moved / dt;
+            }
+        }
+        if let Some(boss) = &mut self.boss {
+            boss.update(dt);
+        }
+    }
+
+    /// Puts every formation back where its motion started, bricks and all.
+    pub fn rewind_motion(&mut self) {
+        for formation in self.formations.iter_mut() {
+            for &i in &formation.bricks {
+                self.bricks[i].position -= formation.travelled;
             }
+            formation.time = 0.0;
+            formation.offset = glm::vec2(0.0, 0.0);
+            formation.travelled = glm::vec2(0.0, 0.0);
         }
     }
 
     pub fn draw(&self, renderer: &SpriteRenderer) {
         for brick in &self.bricks {
-            if !brick.destroyed {
+            // destroyed bricks stay visible while their shatter animation plays
+            let shattering = brick
+                .animation
+                .as_ref()
+                .is_some_and(|animation| !animation.is_finished());
+            if !brick.destroyed || shattering {
                 brick.draw(renderer);
             }
         }
+        if let Some(boss) = &self.boss {
+            boss.draw(renderer);
+        }
     }
 
     pub fn is_completed(&self) -> bool {
+        if self.boss.as_ref().is_some_and(|boss| !boss.is_defeated()) {
+            return false;
+        }
         for tile in self.bricks.iter() {
             if !tile.is_solid && !tile.destroyed {
                 return false;
@@ -128,3 +305,61 @@ impl GameLevel {
         true
     }
 }
+
+// the brick for a tile of a level file, if the tile has one
+fn make_brick(
+    tile: u32,
+    pos: glm::TVec2<f32>,
+    size: glm::TVec2<f32>,
+    resource_manager: &ResourceManager,
+) -> Option<GameObject> {
+    if tile == 1 {
+        //solid
+        let mut solid_brick = GameObject::new(
+            pos,
+            size,
+            glm::vec2(0.0, 0.0),
+            resource_manager.get_texture("block_solid"),
+            glm::vec3(0.8, 0.8, 0.7),
+        );
+        solid_brick.is_solid = true;
+        Some(solid_brick)
+    } else if tile > 1 {
+        //non solid
+        let mut color = glm::vec3(1.0, 1.0, 1.0); // original: white
+        // points by colour follow the arcade: yellow 1, green 3, orange 5, red 7
+        let mut points = 1;
+        match tile {
+            2 => {
+                color = glm::vec3(0.2, 0.6, 1.0);
+            }
+            3 => {
+                color = glm::vec3(0.0, 0.7, 0.0);
+                points = 3;
+            }
+            4 => {
+                color = glm::vec3(0.8, 0.8, 0.4);
+            }
+            5 => {
+                color = glm::vec3(1.0, 0.5, 0.0);
+                points = 5;
+            }
+            6 => {
+                color = glm::vec3(0.9, 0.15, 0.1);
+                points = 7;
+            }
+            _ => {}
+        }
+        let mut brick = GameObject::new(
+            pos,
+            size,
+            glm::vec2(0.0, 0.0),
+            resource_manager.get_texture("block"),
+            color,
+        );
+        brick.points = points;
+        Some(brick)
+    } else {
+        None
+    }
+}
@@ -3,18 +3,29 @@ use std::rc::Rc;
 use nalgebra_glm as glm;
 
 use crate::{
-    sprite_renderer::SpriteRenderer,
+    animation::Animation,
+    sprite_renderer::{
+        SpriteDesc,
+        SpriteRenderer,
+    },
     texture::Texture2D,
 };
 
+#[derive(Clone)]
 pub struct GameObject {
     pub position: glm::TVec2<f32>,
     pub size: glm::TVec2<f32>,
     pub velocity: glm::TVec2<f32>,
     sprite: Rc<Texture2D>,
     pub color: glm::TVec3<f32>,
+    pub alpha: f32,
+    // degrees, clockwise around the object's centre
+    pub rotation: f32,
     pub is_solid: bool,
     pub destroyed: bool,
+    // score for destroying a brick
+    pub points: u32,
+    pub animation: Option<Animation>,
 }
 
 impl GameObject {
@@ -31,12 +42,36 @@ impl GameObject {
             velocity,
             sprite,
             color,
+            alpha: 1.0,
+            rotation: 0.0,
             is_solid: false,
             destroyed: false,
+            points: 0,
+            animation: None,
+        }
+    }
+
+    pub fn set_sprite(&mut self, sprite: Rc<Texture2D>) {
+        self.sprite = sprite;
+    }
+
+    pub fn update_animation(&mut self, dt: f32) {
+        if let Some(animation) = &mut self.animation {
+            animation.update(dt);
         }
     }
 
     pub fn draw(&self, renderer: &SpriteRenderer) {
-        renderer.draw_sprite(&self.sprite, &self.position, &self.size, &self.color);
+        let mut desc = SpriteDesc {
+            rotation: self.rotation,
+            color: glm::vec4(self.color.x, self.color.y, self.color.z, self.alpha),
+            ..SpriteDesc::new(self.position, self.size)
+        };
+        if let Some(animation) = &self.animation {
+            let frame = animation.frame();
+            desc.uv_rect = frame.uv_rect;
+            desc.color = desc.color.component_mul(&frame.tint);
+        }
+        renderer.draw(&self.sprite, &desc);
     }
 }
@@ -0,0 +1,293 @@
+use std::collections::{
+    BTreeMap,
+    HashSet,
+    VecDeque,
+};
+
+pub type GamepadId = usize;
+
+/// Buttons named by their position, like gilrs does: South is A on an Xbox pad, Cross on a
+/// PlayStation one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    Select,
+    Start,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; 12] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::West,
+        GamepadButton::North,
+        GamepadButton::LeftShoulder,
+        GamepadButton::RightShoulder,
+        GamepadButton::Select,
+        GamepadButton::Start,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GamepadButton::South => "Pad A",
+            GamepadButton::East => "Pad B",
+            GamepadButton::West => "Pad X",
+            GamepadButton::North => "Pad Y",
+            GamepadButton::LeftShoulder => "Pad LB",
+            GamepadButton::RightShoulder => "Pad RB",
+            GamepadButton::Select => "Pad Select",
+            GamepadButton::Start => "Pad Start",
+            GamepadButton::DPadUp => "Pad Up",
+            GamepadButton::DPadDown => "Pad Down",
+            GamepadButton::DPadLeft => "Pad Left",
+            GamepadButton::DPadRight => "Pad Right",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    Pressed(GamepadId, GamepadButton),
+    Released(GamepadId, GamepadButton),
+    /// Horizontal position of the left stick, -1.0 (left) to 1.0 (right).
+    StickX(GamepadId, f32),
+}
+
+/// Where gamepad events come from: real hardware, or a script for testing without any.
+pub trait GamepadSource {
+    /// Events since the last call, `dt` seconds ago.
+    fn poll(&mut self, dt: f32) -> Vec<GamepadEvent>;
+}
+
+#[derive(Default)]
+struct PadState {
+    buttons: HashSet<GamepadButton>,
+    stick_x: f32,
+}
+
+/// The state of every connected gamepad, built up from [`GamepadEvent`]s. All pads control the
+/// same paddle.
+#[derive(Default)]
+pub struct Gamepads {
+    pads: BTreeMap<GamepadId, PadState>,
+}
+
+impl Gamepads {
+    pub fn handle(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected(id) => {
+                println!("Gamepad {} connected", id);
+                self.pads.insert(id, PadState::default());
+            }
+            GamepadEvent::Disconnected(id) => {
+                println!("Gamepad {} disconnected", id);
+                self.pads.remove(&id);
+            }
+            GamepadEvent::Pressed(id, button) => {
+                self.pads.entry(id).or_default().buttons.insert(button);
+            }
+            GamepadEvent::Released(id, button) => {
+                self.pads.entry(id).or_default().buttons.remove(&button);
+            }
+            GamepadEvent::StickX(id, x) => {
+                self.pads.entry(id).or_default().stick_x = x.clamp(-1.0, 1.0);
+            }
+        }
+    }
+
+    pub fn is_down(&self, button: GamepadButton) -> bool {
+        self.pads.values().any(|pad| pad.buttons.contains(&button))
+    }
+
+    /// The furthest stick deflection of any pad, with `deadzone` cut off and the rest rescaled
+    /// so the paddle still reaches full speed.
+    pub fn stick_x(&self, deadzone: f32) -> f32 {
+        self.pads
+            .values()
+            .map(|pad| apply_deadzone(pad.stick_x, deadzone))
+            .fold(0.0, |strongest: f32, x| {
+                if x.abs() > strongest.abs() {
+                    x
+                } else {
+                    strongest
+                }
+            })
+    }
+}
+
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    if value.abs() <= deadzone || deadzone >= 1.0 {
+        return 0.0;
+    }
+    value.signum() * (value.abs() - deadzone) / (1.0 - deadzone)
+}
+
+/// Replays a script of gamepad events, one per line as `<seconds> <pad> <event>`:
+///
+/// ```text
+/// # plug in, hold the stick right for a second, then launch
+/// 0.0 0 connect
+/// 0.5 0 stick 1.0
+/// 1.5 0 stick 0.0
+/// 1.5 0 press South
+/// 1.6 0 release South
+/// 3.0 0 disconnect
+/// ```
+pub struct FakeGamepad {
+    time: f32,
+    script: VecDeque<(f32, GamepadEvent)>,
+}
+
+impl FakeGamepad {
+    pub fn load(path: &str) -> Self {
+        let text = std::fs::read_to_string(path).expect("Failed to read gamepad script");
+        Self::parse(&text).expect("Failed to parse gamepad script")
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut script = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let event = parse_script_line(line).ok_or(format!("line {}: '{}'", i + 1, line))?;
+            script.push(event);
+        }
+        // stable, so events at the same time keep their order
+        script.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self {
+            time: 0.0,
+            script: script.into(),
+        })
+    }
+}
+
+fn parse_script_line(line: &str) -> Option<(f32, GamepadEvent)> {
+    let mut parts = line.split_whitespace();
+    let time = parts.next()?.parse().ok()?;
+    let id = parts.next()?.parse().ok()?;
+    let event = match (parts.next()?, parts.next()) {
+        ("connect", None) => GamepadEvent::Connected(id),
+        ("disconnect", None) => GamepadEvent::Disconnected(id),
+        ("press", Some(button)) => GamepadEvent::Pressed(id, parse_button(button)?),
+        ("release", Some(button)) => GamepadEvent::Released(id, parse_button(button)?),
+        ("stick", Some(x)) => GamepadEvent::StickX(id, x.parse().ok()?),
+        _ => return None,
+    };
+    parts.next().is_none().then_some((time, event))
+}
+
+pub fn parse_button(name: &str) -> Option<GamepadButton> {
+    GamepadButton::ALL
+        .into_iter()
+        .find(|button| format!("{:?}", button).eq_ignore_ascii_case(name))
+}
+
+impl GamepadSource for FakeGamepad {
+    fn poll(&mut self, dt: f32) -> Vec<GamepadEvent> {
+        self.time += dt;
+        let mut events = Vec::new();
+        while let Some(&(time, event)) = self.script.front()
+            && time <= self.time
+        {
+            events.push(event);
+            self.script.pop_front();
+        }
+        events
+    }
+}
+
+/// Real gamepads through gilrs. Needs libudev on Linux, so it is behind the `gamepad` feature.
+#[cfg(feature = "gamepad")]
+pub struct GilrsSource {
+    gilrs: gilrs::Gilrs,
+    // pads that were plugged in before we started, reported on the first poll
+    already_connected: Vec<GamepadId>,
+}
+
+#[cfg(feature = "gamepad")]
+impl GilrsSource {
+    pub fn new() -> Option<Self> {
+        match gilrs::Gilrs::new() {
+            Ok(gilrs) => {
+                let already_connected = gilrs.gamepads().map(|(id, _)| id.into()).collect();
+                Some(Self {
+                    gilrs,
+                    already_connected,
+                })
+            }
+            Err(err) => {
+                println!("Gamepads are unavailable: {}", err);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl GamepadSource for GilrsSource {
+    fn poll(&mut self, _dt: f32) -> Vec<GamepadEvent> {
+        use gilrs::{
+            Axis,
+            Button,
+            EventType,
+        };
+
+        fn button(button: Button) -> Option<GamepadButton> {
+            Some(match button {
+                Button::South => GamepadButton::South,
+                Button::East => GamepadButton::East,
+                Button::West => GamepadButton::West,
+                Button::North => GamepadButton::North,
+                Button::LeftTrigger => GamepadButton::LeftShoulder,
+                Button::RightTrigger => GamepadButton::RightShoulder,
+                Button::Select => GamepadButton::Select,
+                Button::Start => GamepadButton::Start,
+                Button::DPadUp => GamepadButton::DPadUp,
+                Button::DPadDown => GamepadButton::DPadDown,
+                Button::DPadLeft => GamepadButton::DPadLeft,
+                Button::DPadRight => GamepadButton::DPadRight,
+                _ => return None,
+            })
+        }
+
+        let mut events = self
+            .already_connected
+            .drain(..)
+            .map(GamepadEvent::Connected)
+            .collect::<Vec<_>>();
+        while let Some(event) = self.gilrs.next_event() {
+            let id = event.id.into();
+            let event = match event.event {
+                EventType::Connected => Some(GamepadEvent::Connected(id)),
+                EventType::Disconnected => Some(GamepadEvent::Disconnected(id)),
+                EventType::ButtonPressed(pressed, _) => {
+                    button(pressed).map(|pressed| GamepadEvent::Pressed(id, pressed))
+                }
+                EventType::ButtonReleased(released, _) => {
+                    button(released).map(|released| GamepadEvent::Released(id, released))
+                }
+                EventType::AxisChanged(Axis::LeftStickX, x, _) => Some(GamepadEvent::StickX(id, x)),
+                _ => None,
+            };
+            events.extend(event);
+        }
+        events
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::read_to_string,
+};
+
+/// Minimal reader and writer for the `key = value` files with `[section]` headers the game keeps
+/// its configuration in. Lines starting with `#` or `;` are comments.
+#[derive(Default)]
+pub struct IniFile {
+    sections: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl IniFile {
+    /// Reads `path`, or returns an empty file if it does not exist or cannot be read.
+    pub fn load(path: &str) -> Self {
+        match read_to_string(path) {
+            Ok(contents) => Self::parse(&contents),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut ini = Self::default();
+        let mut section = String::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+            } else if let Some((key, value)) = line.split_once('=') {
+                ini.sections
+                    .entry(section.clone())
+                    .or_default()
+                    .insert(key.trim().to_string(), value.trim().to_string());
+            } else {
+                println!("Ignoring malformed settings line: {}", line);
+            }
+        }
+
+        ini
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.sections
+            .get(section)
+            .and_then(|entries| entries.get(key))
+            .map(String::as_str)
+    }
+
+    pub fn set(&mut self, section: &str, key: &str, value: impl ToString) {
+        self.sections
+            .entry(section.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+    }
+
+    /// Parses the value of `key`, falling back to `default` (with a warning) when it is missing
+    /// or does not parse.
+    pub fn get_or<T: std::str::FromStr>(&self, section: &str, key: &str, default: T) -> T {
+        match self.get(section, key) {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                println!(
+                    "Invalid value '{}' for [{}] {}, using default",
+                    value, section, key
+                );
+                default
+            }),
+            None => default,
+        }
+    }
+}
+
+impl fmt::Display for IniFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (section, entries)) in self.sections.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{}]", section)?;
+            for (key, value) in entries {
+                writeln!(f, "{} = {}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sections_comments_and_values() {
+        let ini = IniFile::parse(
+            "# comment\n\
+             top = level\n\
+             [window]\n\
+             ; another comment\n\
+             width =  1280 \n\
+             title = Breakout = fun\n\
+             not a setting\n\
+             [ audio ]\n\
+             volume=0.5\n",
+        );
+        assert_eq!(ini.get("", "top"), Some("level"));
+        assert_eq!(ini.get("window", "width"), Some("1280"));
+        assert_eq!(ini.get("window", "title"), Some("Breakout = fun"));
+        assert_eq!(ini.get("audio", "volume"), Some("0.5"));
+        assert_eq!(ini.get("window", "height"), None);
+        assert_eq!(ini.get("video", "width"), None);
+    }
+
+    #[test]
+    fn get_or_falls_back_on_missing_or_invalid_values() {
+        let ini = IniFile::parse("[game]\nlives = 3\nspeed = fast\n");
+        assert_eq!(ini.get_or("game", "lives", 5u32), 3);
+        assert_eq!(ini.get_or("game", "speed", 1.5f32), 1.5);
+        assert_eq!(ini.get_or("game", "missing", 7u32), 7);
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        let mut ini = IniFile::default();
+        ini.set("window", "width", 800);
+        ini.set("window", "mode", "borderless");
+        ini.set("audio", "volume", 0.25);
+        let parsed = IniFile::parse(&ini.to_string());
+        assert_eq!(parsed.get("window", "width"), Some("800"));
+        assert_eq!(parsed.get("window", "mode"), Some("borderless"));
+        assert_eq!(parsed.get("audio", "volume"), Some("0.25"));
+        assert_eq!(parsed.to_string(), ini.to_string());
+    }
+}
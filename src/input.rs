@@ -0,0 +1,509 @@
+use std::fmt;
+
+use winit::keyboard::KeyCode;
+
+use crate::{
+    gamepad::{
+        GamepadButton,
+        parse_button,
+    },
+    ini::IniFile,
+};
+
+/// What the player wants to do, independent of the key (or button) that asked for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Launch,
+    Pause,
+    Restart,
+    Player2Left,
+    Player2Right,
+    Player2Launch,
+    NextLevel,
+    PreviousLevel,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    Confirm,
+    Back,
+    Options,
+}
+
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Launch,
+        Action::Pause,
+        Action::Restart,
+        Action::Player2Left,
+        Action::Player2Right,
+        Action::Player2Launch,
+        Action::NextLevel,
+        Action::PreviousLevel,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuLeft,
+        Action::MenuRight,
+        Action::Confirm,
+        Action::Back,
+        Action::Options,
+    ];
+
+    /// Menu actions and gameplay actions are never active at the same time, so they may share
+    /// keys; within a group a key only triggers one action.
+    pub fn is_menu(&self) -> bool {
+        matches!(
+            self,
+            Action::MenuUp
+                | Action::MenuDown
+                | Action::MenuLeft
+                | Action::MenuRight
+                | Action::Confirm
+                | Action::Back
+                | Action::Options
+        )
+    }
+
+    // the menus, including the rebind screen, can't be used without these
+    fn is_required(&self) -> bool {
+        matches!(self, Action::Confirm | Action::Back)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Launch => "Launch / Fire",
+            Action::Pause => "Pause",
+            Action::Restart => "Restart level",
+            Action::Player2Left => "Player two left",
+            Action::Player2Right => "Player two right",
+            Action::Player2Launch => "Player two launch",
+            Action::NextLevel => "Next level",
+            Action::PreviousLevel => "Previous level",
+            Action::MenuUp => "Menu up",
+            Action::MenuDown => "Menu down",
+            Action::MenuLeft => "Menu left",
+            Action::MenuRight => "Menu right",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+            Action::Options => "Options",
+        }
+    }
+
+    pub fn default_inputs(&self) -> Vec<Input> {
+        use GamepadButton as Pad;
+        use Input::{
+            Button,
+            Key,
+        };
+
+        match self {
+            Action::MoveLeft => vec![
+                Key(KeyCode::KeyA),
+                Key(KeyCode::ArrowLeft),
+                Button(Pad::DPadLeft),
+            ],
+            Action::MoveRight => vec![
+                Key(KeyCode::KeyD),
+                Key(KeyCode::ArrowRight),
+                Button(Pad::DPadRight),
+            ],
+            Action::Launch => vec![Key(KeyCode::Space), Button(Pad::South)],
+            Action::Pause => vec![Key(KeyCode::Escape), Key(KeyCode::KeyP), Button(Pad::Start)],
+            Action::Restart => vec![Key(KeyCode::KeyR), Button(Pad::Select)],
+            // the right hand's keys, clear of player one's
+            Action::Player2Left => vec![Key(KeyCode::KeyJ)],
+            Action::Player2Right => vec![Key(KeyCode::KeyL)],
+            Action::Player2Launch => vec![Key(KeyCode::KeyI)],
+            Action::NextLevel => vec![Key(KeyCode::KeyW), Button(Pad::RightShoulder)],
+            Action::PreviousLevel => vec![Key(KeyCode::KeyS), Button(Pad::LeftShoulder)],
+            Action::MenuUp => vec![
+                Key(KeyCode::ArrowUp),
+                Key(KeyCode::KeyW),
+                Button(Pad::DPadUp),
+            ],
+            Action::MenuDown => vec![
+                Key(KeyCode::ArrowDown),
+                Key(KeyCode::KeyS),
+                Button(Pad::DPadDown),
+            ],
+            Action::MenuLeft => vec![
+                Key(KeyCode::ArrowLeft),
+                Key(KeyCode::KeyA),
+                Button(Pad::DPadLeft),
+            ],
+            Action::MenuRight => vec![
+                Key(KeyCode::ArrowRight),
+                Key(KeyCode::KeyD),
+                Button(Pad::DPadRight),
+            ],
+            Action::Confirm => vec![
+                Key(KeyCode::Enter),
+                Key(KeyCode::Space),
+                Button(Pad::South),
+                Button(Pad::Start),
+            ],
+            Action::Back => vec![Key(KeyCode::Escape), Button(Pad::East)],
+            Action::Options => vec![Key(KeyCode::KeyO), Button(Pad::North)],
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // same spelling as the variant, used as the key in the settings file
+        write!(f, "{:?}", self)
+    }
+}
+
+// keys that can be bound and written to the settings file
+const BINDABLE_KEYS: [KeyCode; 70] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadEnter,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Minus,
+];
+
+/// A key or gamepad button that can be bound to an [`Action`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(KeyCode),
+    Button(GamepadButton),
+}
+
+impl Input {
+    /// The name written to the settings file: the winit key name, or the button prefixed with
+    /// "Gamepad", e.g. "KeyA" or "GamepadSouth".
+    pub fn name(&self) -> String {
+        match self {
+            Input::Key(key) => format!("{:?}", key),
+            Input::Button(button) => format!("Gamepad{:?}", button),
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Some(button) = name.strip_prefix("Gamepad") {
+            return parse_button(button).map(Input::Button);
+        }
+        BINDABLE_KEYS
+            .into_iter()
+            .find(|&key| Input::Key(key).name().eq_ignore_ascii_case(name))
+            .map(Input::Key)
+    }
+
+    /// Short name for on-screen prompts, e.g. "W" rather than "KeyW".
+    pub fn label(&self) -> String {
+        let name = match self {
+            Input::Key(_) => self.name(),
+            Input::Button(button) => return button.label().to_string(),
+        };
+        ["Key", "Digit", "Arrow"]
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix))
+            .map(str::to_string)
+            .unwrap_or(name)
+    }
+
+    pub fn is_bindable(&self) -> bool {
+        match self {
+            Input::Key(key) => BINDABLE_KEYS.contains(key),
+            Input::Button(_) => true,
+        }
+    }
+}
+
+/// The inputs bound to every [`Action`]; an action may have any number of them.
+#[derive(Clone, Debug)]
+pub struct InputBindings {
+    bindings: Vec<(Action, Vec<Input>)>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_inputs()))
+                .collect(),
+        }
+    }
+}
+
+impl InputBindings {
+    /// Reads the `[bindings]` section, one `Action = Input, Input` line per action. Actions that
+    /// are missing keep their defaults, unknown names are skipped, and Confirm or Back left with
+    /// nothing bound go back to their defaults so the menus stay usable.
+    pub fn from_ini(ini: &IniFile) -> Self {
+        let mut bindings = Self::default();
+        for (action, inputs) in bindings.bindings.iter_mut() {
+            let Some(value) = ini.get("bindings", &action.to_string()) else {
+                continue;
+            };
+            *inputs = value
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .filter_map(|name| {
+                    let input = Input::parse(name);
+                    if input.is_none() {
+                        println!("Unknown input '{}' bound to {}, ignoring it", name, action);
+                    }
+                    input
+                })
+                .collect();
+            if inputs.is_empty() && action.is_required() {
+                println!(
+                    "{} must keep at least one input, using the default",
+                    action.label()
+                );
+                *inputs = action.default_inputs();
+            }
+        }
+        bindings
+    }
+
+    pub fn to_ini(&self, ini: &mut IniFile) {
+        for (action, inputs) in &self.bindings {
+            let names = inputs
+                .iter()
+                .map(Input::name)
+                .collect::<Vec<_>>()
+                .join(", ");
+            ini.set("bindings", &action.to_string(), names);
+        }
+    }
+
+    pub fn inputs(&self, action: Action) -> &[Input] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map(|(_, inputs)| inputs.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Every action `input` triggers, menu and gameplay alike.
+    pub fn actions_for(&self, input: Input) -> Vec<Action> {
+        self.bindings
+            .iter()
+            .filter(|(_, inputs)| inputs.contains(&input))
+            .map(|(action, _)| *action)
+            .collect()
+    }
+
+    /// The inputs of `action` for prompts, e.g. "Enter/Space", or "-" when nothing is bound.
+    pub fn describe(&self, action: Action) -> String {
+        let inputs = self.inputs(action);
+        if inputs.is_empty() {
+            return "-".to_string();
+        }
+        inputs
+            .iter()
+            .map(Input::label)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Like [`describe`](Self::describe), leaving out gamepad buttons.
+    pub fn describe_keys(&self, action: Action) -> String {
+        let keys = self
+            .inputs(action)
+            .iter()
+            .filter(|input| matches!(input, Input::Key(_)))
+            .map(Input::label)
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            "-".to_string()
+        } else {
+            keys.join("/")
+        }
+    }
+
+    pub fn is_down(&self, action: Action, is_pressed: impl Fn(Input) -> bool) -> bool {
+        self.inputs(action).iter().any(|&input| is_pressed(input))
+    }
+
+    /// Adds `input` to `action`, taking it away from any other action in the same group. Returns
+    /// false without changing anything when that would leave Confirm or Back without an input.
+    pub fn bind(&mut self, action: Action, input: Input) -> bool {
+        let steals_last_input = self.bindings.iter().any(|(other, inputs)| {
+            *other != action
+                && other.is_required()
+                && other.is_menu() == action.is_menu()
+                && inputs.as_slice() == [input]
+        });
+        if steals_last_input {
+            println!("{} is the only input left for a menu action", input.label());
+            return false;
+        }
+
+        for (other, inputs) in self.bindings.iter_mut() {
+            if *other != action && other.is_menu() == action.is_menu() {
+                inputs.retain(|&bound| bound != input);
+            }
+        }
+        if let Some((_, inputs)) = self.bindings.iter_mut().find(|(bound, _)| *bound == action)
+            && !inputs.contains(&input)
+        {
+            inputs.push(input);
+        }
+        true
+    }
+
+    /// Removes every input from `action`, except for Confirm and Back. Returns whether it did.
+    pub fn clear(&mut self, action: Action) -> bool {
+        if action.is_required() {
+            println!("{} must keep at least one input", action.label());
+            return false;
+        }
+        if let Some((_, inputs)) = self.bindings.iter_mut().find(|(bound, _)| *bound == action) {
+            inputs.clear();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_takes_the_input_from_its_group_only() {
+        let mut bindings = InputBindings::default();
+        let key_a = Input::Key(KeyCode::KeyA);
+        assert!(bindings.bind(Action::Launch, key_a));
+        assert!(bindings.inputs(Action::Launch).contains(&key_a));
+        assert!(!bindings.inputs(Action::MoveLeft).contains(&key_a));
+        // menu actions are a group of their own
+        assert!(bindings.inputs(Action::MenuLeft).contains(&key_a));
+
+        // binding twice doesn't list the input twice
+        assert!(bindings.bind(Action::Launch, key_a));
+        let count = bindings
+            .inputs(Action::Launch)
+            .iter()
+            .filter(|&&input| input == key_a);
+        assert_eq!(count.count(), 1);
+    }
+
+    #[test]
+    fn bind_refuses_to_take_the_last_input_of_confirm_or_back() {
+        let mut bindings = InputBindings::default();
+        let escape = Input::Key(KeyCode::Escape);
+        let east = Input::Button(GamepadButton::East);
+        assert!(bindings.bind(Action::Confirm, escape));
+        assert_eq!(bindings.inputs(Action::Back), [east]);
+        assert!(!bindings.bind(Action::Options, east));
+        assert_eq!(bindings.inputs(Action::Back), [east]);
+        assert!(!bindings.inputs(Action::Options).contains(&east));
+    }
+
+    #[test]
+    fn clear_empties_all_but_confirm_and_back() {
+        let mut bindings = InputBindings::default();
+        assert!(bindings.clear(Action::Launch));
+        assert!(bindings.inputs(Action::Launch).is_empty());
+        assert!(!bindings.clear(Action::Confirm));
+        assert!(!bindings.clear(Action::Back));
+        assert_eq!(
+            bindings.inputs(Action::Confirm),
+            Action::Confirm.default_inputs()
+        );
+        assert_eq!(bindings.inputs(Action::Back), Action::Back.default_inputs());
+    }
+
+    #[test]
+    fn from_ini_keeps_confirm_and_back_bound() {
+        let ini = IniFile::parse("[bindings]\nConfirm =\nBack = NoSuchKey\nLaunch =\n");
+        let bindings = InputBindings::from_ini(&ini);
+        assert_eq!(
+            bindings.inputs(Action::Confirm),
+            Action::Confirm.default_inputs()
+        );
+        assert_eq!(bindings.inputs(Action::Back), Action::Back.default_inputs());
+        assert!(bindings.inputs(Action::Launch).is_empty());
+    }
+
+    #[test]
+    fn input_names_parse_back() {
+        for input in [
+            Input::Key(KeyCode::KeyA),
+            Input::Key(KeyCode::ArrowLeft),
+            Input::Button(GamepadButton::DPadUp),
+        ] {
+            assert_eq!(Input::parse(&input.name()), Some(input));
+        }
+        assert_eq!(Input::parse("keya"), Some(Input::Key(KeyCode::KeyA)));
+        assert_eq!(Input::parse("GamepadNothing"), None);
+    }
+}
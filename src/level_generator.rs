@@ -0,0 +1,301 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    str::FromStr,
+};
+
+use crate::rng::Rng;
+
+// tiles as level files have them: 0 empty, 1 solid, 2 and up a brick of that colour
+const EMPTY: u32 = 0;
+const SOLID: u32 = 1;
+// colours from the top band down, like the arcade wall: red, orange, yellow, green, blue
+const BAND_COLORS: [u32; 5] = [6, 5, 4, 3, 2];
+
+/// Whether the right half of a layout mirrors the left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+    None,
+    Mirror,
+}
+
+impl FromStr for Symmetry {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Symmetry::None),
+            "mirror" => Ok(Symmetry::Mirror),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Symmetry::None => "none",
+            Symmetry::Mirror => "mirror",
+        })
+    }
+}
+
+/// How breakable bricks are coloured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Coloring {
+    /// Bands of this many rows each, red at the top down to blue.
+    Bands(usize),
+    Random,
+}
+
+impl FromStr for Coloring {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "random" => Ok(Coloring::Random),
+            rows => match rows.parse() {
+                Ok(rows) if rows > 0 => Ok(Coloring::Bands(rows)),
+                _ => Err(()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Coloring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Coloring::Bands(rows) => write!(f, "{}", rows),
+            Coloring::Random => f.write_str("random"),
+        }
+    }
+}
+
+/// What kind of layout [`generate`] makes.
+#[derive(Clone, Debug)]
+pub struct GeneratorConfig {
+    pub columns: usize,
+    pub rows: usize,
+    /// Share of tiles, 0.0 - 1.0, that get a brick.
+    pub density: f32,
+    /// Share of those bricks, 0.0 - 1.0, that are solid.
+    pub solid_ratio: f32,
+    pub symmetry: Symmetry,
+    pub coloring: Coloring,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            columns: 13,
+            rows: 8,
+            density: 0.7,
+            solid_ratio: 0.1,
+            symmetry: Symmetry::Mirror,
+            coloring: Coloring::Bands(2),
+        }
+    }
+}
+
+/// Generates the tiles of a level, one row per `Vec`, from `seed`. The same seed and config always
+/// give the same layout. Every breakable brick can be reached by the ball, from below, without
+/// going through solid bricks, and there is at least one of them.
+pub fn generate(config: &GeneratorConfig, seed: u64) -> Vec<Vec<u32>> {
+    let mut rng = Rng::new(seed);
+    let columns = config.columns.max(1);
+    let rows = config.rows.max(1);
+    let mut tiles = vec![vec![EMPTY; columns]; rows];
+
+    let generated_columns = match config.symmetry {
+        Symmetry::None => columns,
+        Symmetry::Mirror => columns.div_ceil(2),
+    };
+    for y in 0..rows {
+        for x in 0..generated_columns {
+            if !rng.chance(config.density) {
+                continue;
+            }
+            let tile = if rng.chance(config.solid_ratio) {
+                SOLID
+            } else {
+                brick_color(config.coloring, y, &mut rng)
+            };
+            set_tile(&mut tiles, config.symmetry, x, y, tile);
+        }
+    }
+
+    // break open the walls around sealed bricks, one solid brick at a time, so the layout keeps
+    // as much of its character as it can
+    while let Some((x, y)) = sealed_solid(&tiles) {
+        let tile = brick_color(config.coloring, y, &mut rng);
+        set_tile(&mut tiles, config.symmetry, x, y, tile);
+    }
+
+    if !tiles.iter().flatten().any(|&tile| tile > SOLID) {
+        let tile = brick_color(config.coloring, rows - 1, &mut rng);
+        set_tile(
+            &mut tiles,
+            config.symmetry,
+            (columns - 1) / 2,
+            rows - 1,
+            tile,
+        );
+    }
+    tiles
+}
+
+/// Writes tiles in the level file format, after a `#` comment line for each line of `comment`.
+pub fn to_level_file(tiles: &[Vec<u32>], comment: &str) -> String {
+    let mut contents = String::new();
+    for line in comment.lines() {
+        contents += &format!("# {}\n", line);
+    }
+    for row in tiles {
+        let row = row.iter().map(u32::to_string).collect::<Vec<_>>();
+        contents += &row.join(" ");
+        contents.push('\n');
+    }
+    contents
+}
+
+fn brick_color(coloring: Coloring, row: usize, rng: &mut Rng) -> u32 {
+    match coloring {
+        Coloring::Bands(rows) => BAND_COLORS[row / rows.max(1) % BAND_COLORS.len()],
+        Coloring::Random => BAND_COLORS[rng.below(BAND_COLORS.len() as u32) as usize],
+    }
+}
+
+// sets a tile and, for mirrored layouts, its twin on the other side
+fn set_tile(tiles: &mut [Vec<u32>], symmetry: Symmetry, x: usize, y: usize, tile: u32) {
+    let columns = tiles[y].len();
+    tiles[y][x] = tile;
+    if symmetry == Symmetry::Mirror {
+        tiles[y][columns - 1 - x] = tile;
+    }
+}
+
+// a solid brick in the wall around breakable bricks the ball cannot reach, if there are any: one
+// that opens their pocket up to where the ball already gets, or with more solid bricks behind it,
+// the lowest one so the way out heads for the paddle
+fn sealed_solid(tiles: &[Vec<u32>]) -> Option<(usize, usize)> {
+    let reached = reachable(tiles);
+    let (rows, columns) = (tiles.len(), tiles[0].len());
+    let sealed = (0..rows)
+        .flat_map(|y| (0..columns).map(move |x| (x, y)))
+        .find(|&(x, y)| tiles[y][x] > SOLID && !reached[y][x])?;
+
+    // the whole pocket, empty tiles too, and the solid bricks walling it in
+    let mut pocket = vec![vec![false; columns]; rows];
+    pocket[sealed.1][sealed.0] = true;
+    let mut queue = VecDeque::from([sealed]);
+    let mut walls = Vec::new();
+    while let Some((x, y)) = queue.pop_front() {
+        for (nx, ny) in neighbours(x, y, columns, rows) {
+            if tiles[ny][nx] == SOLID {
+                walls.push((nx, ny));
+            } else if !pocket[ny][nx] {
+                pocket[ny][nx] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    let opens_up = |&(x, y): &(usize, usize)| {
+        y == rows - 1 || neighbours(x, y, columns, rows).any(|(nx, ny)| reached[ny][nx])
+    };
+    walls
+        .iter()
+        .copied()
+        .find(opens_up)
+        .or_else(|| walls.iter().copied().max_by_key(|&(_, y)| y))
+}
+
+// tiles the ball can get to, coming in from below and breaking bricks on the way; the sides are
+// walls and the top is the ceiling, so the bottom row is the only way in
+fn reachable(tiles: &[Vec<u32>]) -> Vec<Vec<bool>> {
+    let (rows, columns) = (tiles.len(), tiles[0].len());
+    let mut reached = vec![vec![false; columns]; rows];
+    let mut queue = VecDeque::new();
+    for x in 0..columns {
+        if tiles[rows - 1][x] != SOLID {
+            reached[rows - 1][x] = true;
+            queue.push_back((x, rows - 1));
+        }
+    }
+    while let Some((x, y)) = queue.pop_front() {
+        for (nx, ny) in neighbours(x, y, columns, rows) {
+            if !reached[ny][nx] && tiles[ny][nx] != SOLID {
+                reached[ny][nx] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    reached
+}
+
+// the tiles left, right, above and below, where there are any; the ball is too big to squeeze
+// between two diagonal bricks
+fn neighbours(
+    x: usize,
+    y: usize,
+    columns: usize,
+    rows: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    [(-1, 0), (1, 0), (0, -1), (0, 1)]
+        .into_iter()
+        .filter_map(move |(dx, dy): (isize, isize)| {
+            let nx = x.checked_add_signed(dx)?;
+            let ny = y.checked_add_signed(dy)?;
+            (nx < columns && ny < rows).then_some((nx, ny))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_layout() {
+        let config = GeneratorConfig {
+            symmetry: Symmetry::None,
+            coloring: Coloring::Random,
+            ..GeneratorConfig::default()
+        };
+        assert_eq!(generate(&config, 1234), generate(&config, 1234));
+        assert_ne!(generate(&config, 1234), generate(&config, 1235));
+    }
+
+    #[test]
+    fn every_breakable_brick_is_reachable() {
+        for density in [0.3, 0.5, 0.7, 1.0] {
+            for solid_ratio in [0.0, 0.1, 0.3, 0.6, 0.9] {
+                for symmetry in [Symmetry::None, Symmetry::Mirror] {
+                    let config = GeneratorConfig {
+                        density,
+                        solid_ratio,
+                        symmetry,
+                        ..GeneratorConfig::default()
+                    };
+                    for seed in 0..300 {
+                        let tiles = generate(&config, seed);
+                        let reached = reachable(&tiles);
+                        let bricks = tiles.iter().flatten().zip(reached.iter().flatten());
+                        assert!(
+                            bricks.clone().any(|(&tile, _)| tile > SOLID),
+                            "no breakable brick with seed {} in {:?}",
+                            seed,
+                            config
+                        );
+                        assert!(
+                            bricks
+                                .filter(|&(&tile, _)| tile > SOLID)
+                                .all(|(_, &reached)| reached),
+                            "sealed brick with seed {} in {:?}",
+                            seed,
+                            config
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,6 @@
+//! The parts of the game that don't need a window, shared by the game and the tools in
+//! `src/bin`.
+
+pub mod level_generator;
+pub mod netplay;
+pub mod rng;
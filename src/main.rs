@@ -6,21 +6,66 @@
 //     clippy::cargo,
 // )]
 
+mod animation;
 mod ball_object;
+mod boss;
+mod brick_motion;
+mod controls_menu;
+mod daily;
+mod endless;
 mod event_handler;
 mod game;
 mod game_level;
 mod game_object;
+mod gamepad;
+mod ini;
+mod input;
+mod options_menu;
+mod projectile;
+mod records;
 mod resource_manager;
+mod settings;
 mod shader;
+mod spectator;
+mod speed_ramp;
 mod sprite_renderer;
+mod text_renderer;
 mod texture;
+mod texture_atlas;
+mod time_attack;
+mod two_player;
+mod viewport;
 mod window;
 
-use game::Game;
+use std::time::Duration;
+
+use breakout_rs::{
+    level_generator,
+    netplay,
+    rng,
+};
+use game::{
+    Game,
+    GameMode,
+};
 
 use crate::{
+    daily::Replay,
     event_handler::EventHandler,
+    gamepad::{
+        FakeGamepad,
+        GamepadSource,
+    },
+    netplay::{
+        SimulatedLag,
+        Transport,
+        UdpTransport,
+    },
+    settings::{
+        SETTINGS_PATH,
+        Settings,
+    },
+    spectator::SpectatorServer,
     window::Window,
 };
 
@@ -28,18 +73,31 @@ const SCR_WIDTH: u32 = 800;
 const SCR_HEIGHT: u32 = 600;
 
 fn main() {
+    let settings = Settings::load(SETTINGS_PATH);
+
     let Window {
         gl,
         gl_surface,
         gl_context,
         window,
         event_loop,
-    } = Window::build();
+    } = Window::build(&settings.window);
 
-    let mut game = Game::new(gl.clone(), SCR_WIDTH, SCR_HEIGHT);
+    let mut game = Game::new(gl.clone(), SCR_WIDTH, SCR_HEIGHT, settings);
     game.init();
+    if let Some(replay) = replay() {
+        game.play_replay(replay);
+    }
+    network(&mut game);
+    spectate(&mut game);
 
-    let mut event_handler = EventHandler::new(SCR_WIDTH, SCR_HEIGHT);
+    let mut event_handler =
+        EventHandler::new(SCR_WIDTH, SCR_HEIGHT, &game.settings, gamepad_source());
+    // the window is created at its own size and scale factor, fit the game into it
+    let physical_size = window.inner_size();
+    event_handler
+        .viewport
+        .resize(physical_size.width, physical_size.height);
 
     let _ = event_loop.run(move |event, elwt| {
         event_handler.handle_event(
@@ -53,3 +111,102 @@ fn main() {
         );
     });
 }
+
+// `--replay <file>` watches a recorded daily challenge attempt
+fn replay() -> Option<Replay> {
+    let args = std::env::args().collect::<Vec<_>>();
+    let i = args.iter().position(|arg| arg == "--replay")?;
+    let path = args.get(i + 1).expect("--replay needs a replay path");
+    // a replay that can't be read leaves the game to be played as usual
+    Replay::load(path)
+        .inspect_err(|err| println!("{}", err))
+        .ok()
+}
+
+// `--host <port> [--coop]` hosts a network game, versus unless asked for co-op, and
+// `--join <address>` joins one. `--net-lag <ms>`, `--net-jitter <ms>` and `--net-loss <0.0-1.0>`
+// make the connection worse than it is, to try a game between two copies on one machine.
+fn network(game: &mut Game) {
+    let args = std::env::args().collect::<Vec<_>>();
+    let value = |flag: &str| {
+        let i = args.iter().position(|arg| arg == flag)?;
+        let value = args
+            .get(i + 1)
+            .unwrap_or_else(|| panic!("{} needs a value", flag));
+        Some(value.as_str())
+    };
+    let number = |flag: &str| {
+        value(flag).map(|value| {
+            value
+                .parse::<f32>()
+                .unwrap_or_else(|_| panic!("{} needs a number", flag))
+        })
+    };
+
+    let (mut transport, host): (Box<dyn Transport>, bool) = if let Some(port) = value("--host") {
+        let port = port.parse().expect("--host needs a port");
+        let transport = UdpTransport::host(port).expect("Failed to open the port");
+        println!("Hosting on port {}, waiting for the other player", port);
+        (Box::new(transport), true)
+    } else if let Some(address) = value("--join") {
+        let transport = UdpTransport::join(address).expect("Failed to reach the host");
+        println!("Joining {}", address);
+        (Box::new(transport), false)
+    } else {
+        return;
+    };
+
+    let latency = number("--net-lag").unwrap_or(0.0);
+    let jitter = number("--net-jitter").unwrap_or(0.0);
+    let loss = number("--net-loss").unwrap_or(0.0);
+    if latency > 0.0 || jitter > 0.0 || loss > 0.0 {
+        transport = Box::new(SimulatedLag::new(
+            transport,
+            Duration::from_secs_f32(latency / 1000.0),
+            Duration::from_secs_f32(jitter / 1000.0),
+            loss,
+        ));
+    }
+
+    if host {
+        let coop = args.iter().any(|arg| arg == "--coop");
+        let mode = if coop {
+            GameMode::Coop
+        } else {
+            GameMode::Versus
+        };
+        game.host_online(transport, mode);
+    } else {
+        game.join_online(transport);
+    }
+}
+
+// `--spectate <port>` streams the game as lines of JSON to whoever connects to that port
+fn spectate(game: &mut Game) {
+    let args = std::env::args().collect::<Vec<_>>();
+    let Some(i) = args.iter().position(|arg| arg == "--spectate") else {
+        return;
+    };
+    let port = args
+        .get(i + 1)
+        .and_then(|port| port.parse().ok())
+        .expect("--spectate needs a port");
+    let spectators = SpectatorServer::bind(port).expect("Failed to open the spectator port");
+    println!("Streaming the game on 127.0.0.1:{}", port);
+    game.stream_to(spectators);
+}
+
+// `--fake-gamepad <script>` replays a script of gamepad events instead of reading real pads
+fn gamepad_source() -> Option<Box<dyn GamepadSource>> {
+    let args = std::env::args().collect::<Vec<_>>();
+    if let Some(i) = args.iter().position(|arg| arg == "--fake-gamepad") {
+        let path = args.get(i + 1).expect("--fake-gamepad needs a script path");
+        return Some(Box::new(FakeGamepad::load(path)));
+    }
+
+    #[cfg(feature = "gamepad")]
+    if let Some(source) = gamepad::GilrsSource::new() {
+        return Some(Box::new(source));
+    }
+    None
+}
@@ -0,0 +1,660 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io,
+    net::{
+        SocketAddr,
+        ToSocketAddrs,
+        UdpSocket,
+    },
+    rc::Rc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::rng::Rng;
+
+/// Length of one step of a network game, in seconds. Both sides step the same simulation at
+/// this rate, whatever their frame rate.
+pub const TICK: f32 = 1.0 / 60.0;
+// ticks a local input waits before it is played, so it usually reaches the peer in time
+const INPUT_DELAY: usize = 2;
+// ticks the simulation may run on guessed remote input before it waits for the peer
+const MAX_PREDICTION: usize = 8;
+// ticks one update may catch up on after a slow frame or a wait
+const MAX_TICKS_PER_UPDATE: usize = 4;
+// the oldest inputs the peer hasn't acknowledged are sent again with every packet, up to this many
+const MAX_INPUTS_PER_PACKET: usize = 64;
+const HELLO_INTERVAL: Duration = Duration::from_millis(100);
+// how long the peer may stay silent before the game is given up on
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+const MAGIC: &[u8; 3] = b"BK1";
+const HELLO: u8 = 0;
+const JOIN: u8 = 1;
+const INPUTS: u8 = 2;
+
+/// What one player did during one tick, as it is sent to the other side.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TickInput {
+    // the paddle axis in steps of 1/127, so both sides play exactly the same value
+    axis: i8,
+    pub launch: bool,
+}
+
+impl TickInput {
+    pub fn new(axis: f32, launch: bool) -> Self {
+        Self {
+            axis: (axis.clamp(-1.0, 1.0) * 127.0).round() as i8,
+            launch,
+        }
+    }
+
+    /// -1.0 (full speed left) to 1.0 (full speed right).
+    pub fn axis(&self) -> f32 {
+        self.axis as f32 / 127.0
+    }
+}
+
+/// A game both sides of a network game step in lockstep. Given the same inputs from the same
+/// state it has to end up in the same state, on either machine, for rollback to work.
+pub trait Simulation {
+    type State;
+
+    fn save_state(&self) -> Self::State;
+    fn load_state(&mut self, state: &Self::State);
+    /// Steps the game by one [`TICK`] with each player's input, by player index.
+    fn advance(&mut self, inputs: [TickInput; 2]);
+}
+
+/// Carries packets to and from the other side. Packets may be lost, duplicated or arrive out of
+/// order; the session copes with all of it.
+pub trait Transport {
+    fn send(&mut self, packet: &[u8]);
+    /// The next packet that has arrived, without waiting for one.
+    fn recv(&mut self) -> Option<Vec<u8>>;
+}
+
+/// Packets over UDP. A host learns the peer's address from the first packet it gets.
+pub struct UdpTransport {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+}
+
+impl UdpTransport {
+    /// Waits for a peer on `port`.
+    pub fn host(port: u16) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, peer: None })
+    }
+
+    /// Talks to the host at `address`, as in `192.168.1.20:7777`.
+    pub fn join(address: &str) -> io::Result<Self> {
+        let peer = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such address"))?;
+        let local = if peer.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            peer: Some(peer),
+        })
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, packet: &[u8]) {
+        if let Some(peer) = self.peer {
+            // a full buffer or an unreachable peer only loses the packet, which is resent
+            let _ = self.socket.send_to(packet, peer);
+        }
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        let mut buffer = [0; 1500];
+        loop {
+            let (len, from) = self.socket.recv_from(&mut buffer).ok()?;
+            match self.peer {
+                Some(peer) if peer != from => continue,
+                _ => self.peer = Some(from),
+            }
+            return Some(buffer[..len].to_vec());
+        }
+    }
+}
+
+/// Makes another transport behave like a worse network: every packet sent is held back for
+/// `latency`, give or take up to `jitter`, and `loss` of them (0.0 to 1.0) are dropped. Two
+/// copies of the game on one machine can then play as if they were far apart.
+pub struct SimulatedLag {
+    inner: Box<dyn Transport>,
+    latency: Duration,
+    jitter: Duration,
+    loss: f32,
+    rng: Rng,
+    // packets waiting to go out, and when
+    pending: Vec<(Instant, Vec<u8>)>,
+}
+
+impl SimulatedLag {
+    pub fn new(inner: Box<dyn Transport>, latency: Duration, jitter: Duration, loss: f32) -> Self {
+        Self {
+            inner,
+            latency,
+            jitter,
+            loss,
+            rng: Rng::new(Rng::seed_from_time()),
+            pending: Vec::new(),
+        }
+    }
+
+    fn flush(&mut self) {
+        let now = Instant::now();
+        let (due, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(at, _)| *at <= now);
+        self.pending = pending;
+        for (_, packet) in due {
+            self.inner.send(&packet);
+        }
+    }
+}
+
+impl Transport for SimulatedLag {
+    fn send(&mut self, packet: &[u8]) {
+        if !self.rng.chance(self.loss) {
+            // jitter can let a later packet overtake an earlier one, as on a real network
+            let delay = self.latency.saturating_sub(self.jitter)
+                + self.jitter.mul_f32(self.rng.next_f32() * 2.0);
+            self.pending.push((Instant::now() + delay, packet.to_vec()));
+        }
+        self.flush();
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        self.flush();
+        self.inner.recv()
+    }
+}
+
+/// An in-memory connection between two ends in one process, each end receiving what the other
+/// sends, in order and without loss. Wrap the ends in [`SimulatedLag`] to make it worse.
+pub struct Loopback {
+    incoming: Rc<RefCell<VecDeque<Vec<u8>>>>,
+    outgoing: Rc<RefCell<VecDeque<Vec<u8>>>>,
+}
+
+impl Loopback {
+    pub fn pair() -> (Self, Self) {
+        let there = Rc::new(RefCell::new(VecDeque::new()));
+        let back = Rc::new(RefCell::new(VecDeque::new()));
+        (
+            Self {
+                incoming: back.clone(),
+                outgoing: there.clone(),
+            },
+            Self {
+                incoming: there,
+                outgoing: back,
+            },
+        )
+    }
+}
+
+impl Transport for Loopback {
+    fn send(&mut self, packet: &[u8]) {
+        self.outgoing.borrow_mut().push_back(packet.to_vec());
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        self.incoming.borrow_mut().pop_front()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NetStatus {
+    /// Still waiting to hear from the other side.
+    Connecting,
+    Running,
+    /// The other side went quiet for too long.
+    Disconnected,
+}
+
+#[derive(Debug, PartialEq)]
+enum Packet {
+    // the host's description of the game, until the peer joins
+    Hello {
+        seed: u64,
+        setup: Vec<u8>,
+    },
+    // the joining side asking for the host's hello
+    Join,
+    // `inputs` from tick `first` on, and how many of the receiver's ticks the sender has
+    Inputs {
+        ack: usize,
+        first: usize,
+        inputs: Vec<TickInput>,
+    },
+}
+
+impl Packet {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        match self {
+            Packet::Hello { seed, setup } => {
+                bytes.push(HELLO);
+                bytes.extend(seed.to_le_bytes());
+                bytes.extend(setup);
+            }
+            Packet::Join => bytes.push(JOIN),
+            Packet::Inputs { ack, first, inputs } => {
+                bytes.push(INPUTS);
+                bytes.extend((*ack as u32).to_le_bytes());
+                bytes.extend((*first as u32).to_le_bytes());
+                for input in inputs {
+                    bytes.push(input.axis as u8);
+                    bytes.push(input.launch as u8);
+                }
+            }
+        }
+        bytes
+    }
+
+    // None for anything that isn't a packet of ours
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.strip_prefix(MAGIC)?;
+        let (&kind, body) = bytes.split_first()?;
+        let u32_at = |at: usize| {
+            let word = body.get(at..at + 4)?;
+            Some(u32::from_le_bytes(word.try_into().ok()?) as usize)
+        };
+        match kind {
+            HELLO => {
+                let (seed, setup) = body.split_at_checked(8)?;
+                Some(Packet::Hello {
+                    seed: u64::from_le_bytes(seed.try_into().ok()?),
+                    setup: setup.to_vec(),
+                })
+            }
+            JOIN => Some(Packet::Join),
+            INPUTS => {
+                let inputs = body.get(8..)?;
+                if !inputs.len().is_multiple_of(2) {
+                    return None;
+                }
+                Some(Packet::Inputs {
+                    ack: u32_at(0)?,
+                    first: u32_at(4)?,
+                    inputs: inputs
+                        .chunks(2)
+                        .map(|pair| TickInput {
+                            axis: pair[0] as i8,
+                            launch: pair[1] != 0,
+                        })
+                        .collect(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One side of a two player network game. Each side plays its own input at once and guesses the
+/// other's from the last it heard; when the real input turns out different, the simulation is
+/// rolled back to the tick it went wrong and played forward again. Inputs are resent until the
+/// peer acknowledges them, so lost packets only cost a little more guessing.
+pub struct NetSession<S: Simulation> {
+    transport: Box<dyn Transport>,
+    // the player this side controls: the host is player one
+    local: usize,
+    // what the host set the game up with, for the joining side once it hears the hello
+    seed: u64,
+    setup: Vec<u8>,
+    connected: bool,
+    last_heard: Instant,
+    last_hello: Option<Instant>,
+    // the next tick to simulate, and the time saved up towards it
+    frame: usize,
+    time: f32,
+    // a launch pressed while the game was waiting on the peer, for the next tick
+    launch_pending: bool,
+    // by tick, `INPUT_DELAY` ahead of `frame`
+    local_inputs: Vec<TickInput>,
+    // by tick, as far as the peer has sent them without a gap
+    remote_inputs: Vec<TickInput>,
+    // how many of our inputs the peer has
+    remote_ack: usize,
+    // the remote input each simulated tick was played with, guessed or not
+    played: Vec<TickInput>,
+    // ticks before this were played with the peer's real input
+    checked: usize,
+    // the state before each tick from `checked` on
+    states: VecDeque<S::State>,
+    rollbacks: u32,
+}
+
+impl<S: Simulation> NetSession<S> {
+    /// Hosts a game as player one; the joining side plays whatever `seed` and `setup` describe.
+    pub fn host(transport: Box<dyn Transport>, seed: u64, setup: Vec<u8>) -> Self {
+        Self::new(transport, 0, seed, setup)
+    }
+
+    /// Joins a hosted game as player two.
+    pub fn join(transport: Box<dyn Transport>) -> Self {
+        Self::new(transport, 1, 0, Vec::new())
+    }
+
+    fn new(transport: Box<dyn Transport>, local: usize, seed: u64, setup: Vec<u8>) -> Self {
+        Self {
+            transport,
+            local,
+            seed,
+            setup,
+            connected: false,
+            last_heard: Instant::now(),
+            last_hello: None,
+            frame: 0,
+            time: 0.0,
+            launch_pending: false,
+            local_inputs: vec![TickInput::default(); INPUT_DELAY],
+            remote_inputs: Vec::new(),
+            remote_ack: 0,
+            played: Vec::new(),
+            checked: 0,
+            states: VecDeque::new(),
+            rollbacks: 0,
+        }
+    }
+
+    pub fn local_player(&self) -> usize {
+        self.local
+    }
+
+    pub fn status(&self) -> NetStatus {
+        if !self.connected {
+            NetStatus::Connecting
+        } else if self.last_heard.elapsed() > TIMEOUT {
+            NetStatus::Disconnected
+        } else {
+            NetStatus::Running
+        }
+    }
+
+    /// The seed and setup the host started the game with, once connected.
+    pub fn setup(&self) -> (u64, &[u8]) {
+        (self.seed, &self.setup)
+    }
+
+    /// The next tick to be simulated.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// How many of the ticks simulated so far ran on guessed remote input.
+    pub fn prediction(&self) -> usize {
+        self.frame - self.remote_inputs.len().min(self.frame)
+    }
+
+    /// How many times the simulation has been rolled back.
+    pub fn rollbacks(&self) -> u32 {
+        self.rollbacks
+    }
+
+    /// Takes in what the peer has sent, and keeps asking for them while connecting.
+    pub fn poll(&mut self) {
+        self.receive();
+        if !self.connected {
+            self.say_hello();
+        }
+    }
+
+    /// Steps `sim` for the `dt` seconds that have passed with the local player's `input`, rolling
+    /// back first if the peer's input polled since showed a guess was wrong, and sends the peer
+    /// the inputs it hasn't got yet.
+    pub fn update(&mut self, sim: &mut S, dt: f32, input: TickInput) {
+        if !self.connected {
+            return;
+        }
+
+        self.roll_back(sim);
+        self.launch_pending |= input.launch;
+        self.time = (self.time + dt).min(TICK * MAX_TICKS_PER_UPDATE as f32);
+        // past the prediction limit the game holds still until the peer catches up
+        while self.time >= TICK && self.frame < self.remote_inputs.len() + MAX_PREDICTION {
+            let launch = std::mem::take(&mut self.launch_pending);
+            self.local_inputs.push(TickInput { launch, ..input });
+            self.step(sim);
+            self.time -= TICK;
+        }
+        self.send_inputs();
+    }
+
+    fn receive(&mut self) {
+        while let Some(bytes) = self.transport.recv() {
+            let Some(packet) = Packet::decode(&bytes) else {
+                continue;
+            };
+            self.last_heard = Instant::now();
+            match packet {
+                Packet::Hello { seed, setup } if self.local == 1 && !self.connected => {
+                    self.seed = seed;
+                    self.setup = setup;
+                    self.connected = true;
+                }
+                Packet::Join if self.local == 0 => {
+                    // our hello may have been lost after the peer first heard from us
+                    self.connected = true;
+                    self.send_hello();
+                }
+                Packet::Inputs { ack, first, inputs } => {
+                    // the joining side only sends inputs once it has the hello
+                    self.connected |= self.local == 0;
+                    self.remote_ack = self.remote_ack.max(ack);
+                    let known = self.remote_inputs.len();
+                    if first <= known && first + inputs.len() > known {
+                        self.remote_inputs.extend(&inputs[known - first..]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn say_hello(&mut self) {
+        if self
+            .last_hello
+            .is_some_and(|at| at.elapsed() < HELLO_INTERVAL)
+        {
+            return;
+        }
+        self.last_hello = Some(Instant::now());
+        if self.local == 0 {
+            self.send_hello();
+        } else {
+            self.transport.send(&Packet::Join.encode());
+        }
+    }
+
+    fn send_hello(&mut self) {
+        let hello = Packet::Hello {
+            seed: self.seed,
+            setup: self.setup.clone(),
+        };
+        self.transport.send(&hello.encode());
+    }
+
+    fn send_inputs(&mut self) {
+        let first = self.remote_ack.min(self.local_inputs.len());
+        let last = self.local_inputs.len().min(first + MAX_INPUTS_PER_PACKET);
+        let packet = Packet::Inputs {
+            ack: self.remote_inputs.len(),
+            first,
+            inputs: self.local_inputs[first..last].to_vec(),
+        };
+        self.transport.send(&packet.encode());
+    }
+
+    // replays the ticks since the first one played with a wrong guess of the peer's input
+    fn roll_back(&mut self, sim: &mut S) {
+        let confirmed = self.remote_inputs.len().min(self.frame);
+        let wrong = (self.checked..confirmed).find(|&f| self.played[f] != self.remote_inputs[f]);
+        if let Some(wrong) = wrong {
+            sim.load_state(&self.states[wrong - self.checked]);
+            self.states.truncate(wrong - self.checked);
+            let end = self.frame;
+            self.frame = wrong;
+            while self.frame < end {
+                self.step(sim);
+            }
+            self.rollbacks += 1;
+        }
+        // nothing before the confirmed ticks can go wrong any more
+        self.states.drain(..confirmed - self.checked);
+        self.checked = confirmed;
+    }
+
+    fn step(&mut self, sim: &mut S) {
+        // the peer is guessed to carry on as they were, but not to launch again
+        let remote = match self.remote_inputs.get(self.frame) {
+            Some(&input) => input,
+            None => TickInput {
+                launch: false,
+                ..self.remote_inputs.last().copied().unwrap_or_default()
+            },
+        };
+        self.played.truncate(self.frame);
+        self.played.push(remote);
+        self.states.push_back(sim.save_state());
+
+        let mut inputs = [remote; 2];
+        inputs[self.local] = self.local_inputs[self.frame];
+        sim.advance(inputs);
+        self.frame += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a paddle per player moved by its axis, and the state after every tick to compare
+    #[derive(Default)]
+    struct Paddles {
+        positions: [i64; 2],
+        launches: [u32; 2],
+        history: Vec<([i64; 2], [u32; 2])>,
+    }
+
+    impl Simulation for Paddles {
+        type State = ([i64; 2], [u32; 2], usize);
+
+        fn save_state(&self) -> Self::State {
+            (self.positions, self.launches, self.history.len())
+        }
+
+        fn load_state(&mut self, state: &Self::State) {
+            (self.positions, self.launches) = (state.0, state.1);
+            self.history.truncate(state.2);
+        }
+
+        fn advance(&mut self, inputs: [TickInput; 2]) {
+            for (player, input) in inputs.iter().enumerate() {
+                self.positions[player] += input.axis as i64;
+                self.launches[player] += input.launch as u32;
+            }
+            self.history.push((self.positions, self.launches));
+        }
+    }
+
+    #[test]
+    fn sessions_agree_on_confirmed_ticks() {
+        let (host, join) = Loopback::pair();
+        let lag = |transport: Loopback| -> Box<dyn Transport> {
+            Box::new(SimulatedLag::new(
+                Box::new(transport),
+                Duration::from_millis(30),
+                Duration::from_millis(15),
+                0.2,
+            ))
+        };
+        let mut sessions = [
+            NetSession::host(lag(host), 42, b"test".to_vec()),
+            NetSession::join(lag(join)),
+        ];
+        let mut sims = [Paddles::default(), Paddles::default()];
+
+        let ticks = 120;
+        let started = Instant::now();
+        let mut last_frame = started;
+        while sessions.iter().any(|session| session.frame() < ticks) {
+            assert!(
+                started.elapsed() < Duration::from_secs(20),
+                "sessions stalled"
+            );
+            std::thread::sleep(Duration::from_secs_f32(TICK / 2.0));
+            let dt = last_frame.elapsed().as_secs_f32();
+            last_frame = Instant::now();
+            for (player, (session, sim)) in sessions.iter_mut().zip(&mut sims).enumerate() {
+                session.poll();
+                // each player changes direction now and then and launches once in a while
+                let frame = session.frame();
+                let axis = if (frame / 13 + player).is_multiple_of(2) {
+                    1.0
+                } else {
+                    -0.5
+                };
+                session.update(sim, dt, TickInput::new(axis, frame.is_multiple_of(29)));
+            }
+        }
+        // let the last inputs through so both sides confirm as much as they can
+        let settle = Instant::now();
+        while settle.elapsed() < Duration::from_millis(500) {
+            std::thread::sleep(Duration::from_secs_f32(TICK / 2.0));
+            for (session, sim) in sessions.iter_mut().zip(&mut sims) {
+                session.poll();
+                session.update(sim, 0.0, TickInput::default());
+            }
+        }
+
+        let [host, join] = &sessions;
+        assert_eq!(join.status(), NetStatus::Running);
+        assert_eq!(join.setup(), (42, &b"test"[..]));
+        let confirmed = sessions
+            .iter()
+            .map(|session| session.frame() - session.prediction())
+            .min()
+            .unwrap();
+        assert!(confirmed > 0, "no tick was confirmed");
+        assert_eq!(sims[0].history[..confirmed], sims[1].history[..confirmed]);
+        assert!(host.frame() >= ticks);
+    }
+
+    #[test]
+    fn packets_round_trip() {
+        let packets = [
+            Packet::Hello {
+                seed: 0x0123_4567_89ab_cdef,
+                setup: b"versus 3".to_vec(),
+            },
+            Packet::Join,
+            Packet::Inputs {
+                ack: 7,
+                first: 300,
+                inputs: vec![TickInput::new(-1.0, false), TickInput::new(0.5, true)],
+            },
+        ];
+        for packet in packets {
+            assert_eq!(Packet::decode(&packet.encode()), Some(packet));
+        }
+        assert_eq!(Packet::decode(b"not ours"), None);
+    }
+}
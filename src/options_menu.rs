@@ -0,0 +1,194 @@
+use crate::{
+    settings::{
+        Difficulty,
+        MSAA_SAMPLES,
+        Rules,
+        Settings,
+    },
+    window::{
+        VsyncMode,
+        WindowMode,
+    },
+};
+
+const PADDLE_SPEED_STEP: f32 = 50.0;
+const VOLUME_STEP: f32 = 0.1;
+const SENSITIVITY_STEP: f32 = 0.1;
+const DEADZONE_STEP: f32 = 0.05;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OptionItem {
+    WindowMode,
+    Vsync,
+    IntegerScaling,
+    Msaa,
+    PaddleSpeed,
+    MouseControl,
+    MouseSensitivity,
+    GrabCursor,
+    StickDeadzone,
+    Difficulty,
+    Rules,
+    CurveBall,
+    MusicVolume,
+    SfxVolume,
+    Controls,
+    Back,
+}
+
+impl OptionItem {
+    const ALL: [OptionItem; 16] = [
+        OptionItem::WindowMode,
+        OptionItem::Vsync,
+        OptionItem::IntegerScaling,
+        OptionItem::Msaa,
+        OptionItem::PaddleSpeed,
+        OptionItem::MouseControl,
+        OptionItem::MouseSensitivity,
+        OptionItem::GrabCursor,
+        OptionItem::StickDeadzone,
+        OptionItem::Difficulty,
+        OptionItem::Rules,
+        OptionItem::CurveBall,
+        OptionItem::MusicVolume,
+        OptionItem::SfxVolume,
+        OptionItem::Controls,
+        OptionItem::Back,
+    ];
+}
+
+/// The options screen reachable from the menu. Every change is made to the live [`Settings`]
+/// straight away; the caller applies and saves them.
+pub struct OptionsMenu {
+    selected: usize,
+}
+
+impl OptionsMenu {
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+
+    pub fn selected(&self) -> OptionItem {
+        OptionItem::ALL[self.selected]
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % OptionItem::ALL.len();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = (self.selected + OptionItem::ALL.len() - 1) % OptionItem::ALL.len();
+    }
+
+    /// Steps the selected option forwards (`step > 0`) or backwards. Returns whether anything
+    /// changed.
+    pub fn adjust(&self, settings: &mut Settings, step: i32) -> bool {
+        match self.selected() {
+            OptionItem::WindowMode => {
+                settings.window.mode = cycle(
+                    &[
+                        WindowMode::Windowed,
+                        WindowMode::Borderless,
+                        WindowMode::Fullscreen,
+                    ],
+                    settings.window.mode,
+                    step,
+                );
+            }
+            OptionItem::Vsync => {
+                settings.window.vsync = cycle(
+                    &[VsyncMode::Off, VsyncMode::On, VsyncMode::Half],
+                    settings.window.vsync,
+                    step,
+                );
+            }
+            OptionItem::IntegerScaling => settings.integer_scaling = !settings.integer_scaling,
+            OptionItem::Msaa => {
+                settings.window.msaa_samples =
+                    cycle(&MSAA_SAMPLES, settings.window.msaa_samples, step);
+            }
+            OptionItem::PaddleSpeed => {
+                settings.paddle_speed += PADDLE_SPEED_STEP * step as f32;
+            }
+            OptionItem::MouseControl => settings.mouse_control = !settings.mouse_control,
+            OptionItem::MouseSensitivity => {
+                settings.mouse_sensitivity += SENSITIVITY_STEP * step as f32;
+            }
+            OptionItem::GrabCursor => settings.grab_cursor = !settings.grab_cursor,
+            OptionItem::StickDeadzone => {
+                settings.stick_deadzone =
+                    ((settings.stick_deadzone + DEADZONE_STEP * step as f32) * 20.0).round() / 20.0;
+            }
+            OptionItem::Difficulty => {
+                settings.difficulty = cycle(&Difficulty::ALL, settings.difficulty, step);
+            }
+            OptionItem::Rules => settings.rules = cycle(&Rules::ALL, settings.rules, step),
+            OptionItem::CurveBall => settings.curve_ball = !settings.curve_ball,
+            OptionItem::MusicVolume => settings.music_volume += VOLUME_STEP * step as f32,
+            OptionItem::SfxVolume => settings.sfx_volume += VOLUME_STEP * step as f32,
+            OptionItem::Controls | OptionItem::Back => return false,
+        }
+        // keep the volumes and sensitivity on clean tenths after repeated steps
+        settings.music_volume = (settings.music_volume * 10.0).round() / 10.0;
+        settings.sfx_volume = (settings.sfx_volume * 10.0).round() / 10.0;
+        settings.mouse_sensitivity = (settings.mouse_sensitivity * 10.0).round() / 10.0;
+        settings.validate();
+        true
+    }
+
+    /// One line per option, paired with whether it is the selected one.
+    pub fn lines(&self, settings: &Settings) -> Vec<(String, bool)> {
+        OptionItem::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let line = match item {
+                    OptionItem::WindowMode => format!("Window mode: {}", settings.window.mode),
+                    OptionItem::Vsync => format!("VSync: {}", settings.window.vsync),
+                    OptionItem::IntegerScaling => {
+                        format!("Integer scaling: {}", on_off(settings.integer_scaling))
+                    }
+                    OptionItem::Msaa => {
+                        format!("MSAA: {}x (after restart)", settings.window.msaa_samples)
+                    }
+                    OptionItem::PaddleSpeed => {
+                        format!("Paddle speed: {:.0}", settings.paddle_speed)
+                    }
+                    OptionItem::MouseControl => {
+                        format!("Mouse control: {}", on_off(settings.mouse_control))
+                    }
+                    OptionItem::MouseSensitivity => {
+                        format!("Mouse sensitivity: {:.1}x", settings.mouse_sensitivity)
+                    }
+                    OptionItem::GrabCursor => {
+                        format!("Grab cursor: {}", on_off(settings.grab_cursor))
+                    }
+                    OptionItem::StickDeadzone => {
+                        format!("Stick deadzone: {:.0}%", settings.stick_deadzone * 100.0)
+                    }
+                    OptionItem::Difficulty => format!("Difficulty: {}", settings.difficulty),
+                    OptionItem::Rules => format!("Rules: {}", settings.rules),
+                    OptionItem::CurveBall => format!("Curve ball: {}", on_off(settings.curve_ball)),
+                    OptionItem::MusicVolume => {
+                        format!("Music volume: {:.0}%", settings.music_volume * 100.0)
+                    }
+                    OptionItem::SfxVolume => {
+                        format!("Effects volume: {:.0}%", settings.sfx_volume * 100.0)
+                    }
+                    OptionItem::Controls => "Controls...".to_string(),
+                    OptionItem::Back => "Back".to_string(),
+                };
+                (line, i == self.selected)
+            })
+            .collect()
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
+
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, step: i32) -> T {
+    let index = values.iter().position(|&v| v == current).unwrap_or(0) as i32;
+    values[(index + step).rem_euclid(values.len() as i32) as usize]
+}
@@ -0,0 +1,78 @@
+use std::rc::Rc;
+
+use nalgebra_glm as glm;
+
+use crate::{
+    game::PaddleMode,
+    game_object::GameObject,
+    sprite_renderer::SpriteRenderer,
+    texture::Texture2D,
+};
+
+/// What a projectile is and what it does to whatever it hits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProjectileKind {
+    /// Fired at the paddle, narrows it until the next life.
+    Shrink,
+    /// Fired at the paddle, costs a life.
+    Lethal,
+    /// Fired by the paddle, destroys the first brick it hits.
+    Laser,
+    /// Falls from a brick and switches the paddle to its mode when caught.
+    Capsule(PaddleMode),
+}
+
+/// A shot or a falling capsule, moving in a straight line until it hits something or leaves the
+/// screen.
+#[derive(Clone)]
+pub struct Projectile {
+    pub game_obj: GameObject,
+    pub kind: ProjectileKind,
+}
+
+impl Projectile {
+    pub fn new(
+        center: glm::TVec2<f32>,
+        size: glm::TVec2<f32>,
+        velocity: glm::TVec2<f32>,
+        kind: ProjectileKind,
+        sprite: Rc<Texture2D>,
+    ) -> Self {
+        let color = match kind {
+            ProjectileKind::Shrink => glm::vec3(1.0, 0.8, 0.2),
+            ProjectileKind::Lethal => glm::vec3(1.0, 0.2, 0.2),
+            ProjectileKind::Laser => glm::vec3(1.0, 0.3, 0.3),
+            ProjectileKind::Capsule(mode) => mode.color(),
+        };
+        Self {
+            game_obj: GameObject::new(center - size / 2.0, size, velocity, sprite, color),
+            kind,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.game_obj.position += self.game_obj.velocity * dt;
+        self.game_obj.update_animation(dt);
+    }
+
+    pub fn is_off_screen(&self, width: u32, height: u32) -> bool {
+        let obj = &self.game_obj;
+        obj.position.x + obj.size.x < 0.0
+            || obj.position.y + obj.size.y < 0.0
+            || obj.position.x > width as f32
+            || obj.position.y > height as f32
+    }
+
+    pub fn overlaps(&self, other: &GameObject) -> bool {
+        // AABB - AABB collision
+        let obj = &self.game_obj;
+        obj.position.x + obj.size.x >= other.position.x
+            && other.position.x + other.size.x >= obj.position.x
+            && obj.position.y + obj.size.y >= other.position.y
+            && other.position.y + other.size.y >= obj.position.y
+    }
+
+    pub fn draw(&self, renderer: &SpriteRenderer) {
+        self.game_obj.draw(renderer);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::ini::IniFile;
+
+pub const RECORDS_PATH: &str = "records.ini";
+
+/// Best results kept between runs, in an ini file next to the settings: the high score of each
+/// category of game, the best time attack times and the daily challenge scores.
+pub struct Records {
+    path: String,
+    ini: IniFile,
+}
+
+impl Records {
+    /// Reads `path`, starting without any records if it is missing.
+    pub fn load(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            ini: IniFile::load(path),
+        }
+    }
+
+    pub fn high_score(&self, category: &str) -> u32 {
+        self.ini.get_or("high_scores", category, 0)
+    }
+
+    /// Keeps `score` if it beats the high score of `category`, saving straight away. Returns
+    /// whether it did.
+    pub fn submit_score(&mut self, category: &str, score: u32) -> bool {
+        if score <= self.high_score(category) {
+            return false;
+        }
+        self.ini.set("high_scores", category, score);
+        self.save();
+        true
+    }
+
+    /// Best time in seconds for `key`, a level file or the whole campaign.
+    pub fn best_time(&self, key: &str) -> Option<f32> {
+        self.ini
+            .get("best_times", key)
+            .and_then(|time| time.parse().ok())
+    }
+
+    /// Keeps `time` if it beats the best time for `key`, saving straight away. Returns whether it
+    /// did.
+    pub fn submit_time(&mut self, key: &str, time: f32) -> bool {
+        if self.best_time(key).is_some_and(|best| best <= time) {
+            return false;
+        }
+        self.ini.set("best_times", key, format!("{:.3}", time));
+        self.save();
+        true
+    }
+
+    /// Score of the scored attempt at the daily challenge of `date`, if it has been played.
+    pub fn daily_score(&self, date: &str) -> Option<u32> {
+        self.ini
+            .get("daily", date)
+            .and_then(|score| score.parse().ok())
+    }
+
+    /// Records the one scored attempt at the daily challenge of `date`, saving straight away.
+    pub fn submit_daily(&mut self, date: &str, score: u32) {
+        self.ini.set("daily", date, score);
+        self.save();
+    }
+
+    fn save(&self) {
+        let contents = format!("# Breakout records, written by the game.\n\n{}", self.ini);
+        if let Err(err) = std::fs::write(&self.path, contents) {
+            println!("Failed to save records to {}: {}", self.path, err);
+        }
+    }
+}
@@ -4,14 +4,27 @@ use std::{
     rc::Rc,
 };
 
-use glow::Context;
-use image::GenericImageView;
+use glow::{
+    Context,
+    HasContext,
+};
+use image::{
+    GenericImageView,
+    Rgba,
+    RgbaImage,
+};
 
 use crate::{
+    animation,
     shader::Shader,
     texture::Texture2D,
+    texture_atlas::AtlasBuilder,
 };
 
+const ATLAS_PAGE_SIZE: u32 = 2048;
+const ATLAS_PADDING: u32 = 2;
+pub const SHATTER_FRAMES: u32 = 4;
+
 pub struct ResourceManager {
     pub gl: Rc<Context>,
     shaders: HashMap<String, Shader>,
@@ -33,17 +46,34 @@ impl ResourceManager {
         fragment_path: &str,
         geometry_path: Option<&str>,
     ) -> Shader {
+        let header = self.glsl_header();
         let vertex_code = read_to_string(vertex_path).expect("Failed to read vertex shader");
         let fragment_code = read_to_string(fragment_path).expect("Failed to read fragment shader");
         let geometry_code = geometry_path
             .map(|geom_path| read_to_string(geom_path).expect("Failed to read geometry shader"));
 
+        let vertex_code = with_glsl_header(vertex_code, header);
+        let fragment_code = with_glsl_header(fragment_code, header);
+        let geometry_code = geometry_code.map(|code| with_glsl_header(code, header));
+
         let shader = Shader::new(self.gl.clone(), vertex_code, fragment_code, geometry_code);
         self.shaders.insert("sprite".to_string(), shader.clone());
 
         shader
     }
 
+    // shaders are written against 4.1 core; older and embedded contexts get their own header
+    fn glsl_header(&self) -> &'static str {
+        let version = self.gl.version();
+        if version.is_embedded {
+            "#version 300 es\nprecision mediump float;"
+        } else if (version.major, version.minor) >= (4, 1) {
+            "#version 410 core"
+        } else {
+            "#version 330 core"
+        }
+    }
+
     pub fn load_texture_from_file(&mut self, path: &str, name: &str) {
         let texture = Texture2D::new(self.gl.clone());
         println!("Loading texture from file: {}", path);
@@ -60,11 +90,59 @@ impl ResourceManager {
         self.textures.insert(name.to_string(), Rc::new(texture));
     }
 
+    /// Packs every `.png` in `dir` into atlas pages and registers each image under its file stem.
+    /// The handles returned by [`ResourceManager::get_texture`] for these names refer to
+    /// sub-rectangles of a shared page texture.
+    pub fn load_atlas_from_dir(&mut self, dir: &str) {
+        let mut builder = AtlasBuilder::new(ATLAS_PAGE_SIZE, ATLAS_PADDING);
+        builder.add_dir(dir);
+        // plain white to tint into bars and overlays
+        builder.add_image(
+            "white",
+            RgbaImage::from_pixel(4, 4, Rgba([255, 255, 255, 255])),
+        );
+        builder.derive_image("block", "block_shatter", |block| {
+            animation::crumble_strip(block, SHATTER_FRAMES)
+        });
+        let atlas = builder.build();
+
+        let pages = atlas
+            .pages
+            .iter()
+            .map(|page| {
+                let texture = Texture2D::new(self.gl.clone());
+                texture.generate(page.width(), page.height(), page.as_raw());
+                texture
+            })
+            .collect::<Vec<_>>();
+
+        println!(
+            "Packed {} textures into {} atlas page(s)",
+            atlas.regions.len(),
+            pages.len()
+        );
+
+        for (name, region) in atlas.regions.iter() {
+            let uv_rect = atlas.uv_rect(name).unwrap();
+            self.textures
+                .insert(name.clone(), Rc::new(pages[region.page].region(uv_rect)));
+        }
+    }
+
     pub fn get_texture(&self, name: &str) -> Rc<Texture2D> {
         self.textures.get(name).unwrap().clone()
     }
 }
 
+fn with_glsl_header(source: String, header: &str) -> String {
+    match source.split_once('\n') {
+        Some((first_line, rest)) if first_line.trim_start().starts_with("#version") => {
+            format!("{header}\n{rest}")
+        }
+        _ => format!("{header}\n{source}"),
+    }
+}
+
 impl Drop for ResourceManager {
     fn drop(&mut self) {
         for (_, shader) in self.shaders.iter() {
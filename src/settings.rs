@@ -0,0 +1,443 @@
+use std::{
+    fmt,
+    str::FromStr,
+};
+
+use crate::{
+    ini::IniFile,
+    input::{
+        Action,
+        Input,
+        InputBindings,
+    },
+    window::WindowConfig,
+};
+
+pub const SETTINGS_PATH: &str = "settings.ini";
+/// Bumped whenever keys are renamed or change meaning; older files are migrated on load.
+pub const SETTINGS_VERSION: u32 = 2;
+
+const MIN_PADDLE_SPEED: f32 = 100.0;
+const MAX_PADDLE_SPEED: f32 = 2000.0;
+const MIN_MOUSE_SENSITIVITY: f32 = 0.1;
+const MAX_MOUSE_SENSITIVITY: f32 = 5.0;
+const MAX_STICK_DEADZONE: f32 = 0.9;
+// outgoing paddle bounces, in degrees above the horizontal
+const MIN_BOUNCE_ANGLE: f32 = 10.0;
+const MAX_BOUNCE_ANGLE: f32 = 90.0;
+pub const MSAA_SAMPLES: [u8; 5] = [0, 2, 4, 8, 16];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn profile(&self) -> DifficultyProfile {
+        match self {
+            Difficulty::Easy => DifficultyProfile {
+                start_speed: 290.0,
+                speed_step: 25.0,
+                max_speed: 520.0,
+                speed_up_interval: 45.0,
+                paddle_width: 1.25,
+                lives: 5,
+                capsule_every: 6,
+            },
+            Difficulty::Normal => DifficultyProfile {
+                start_speed: 360.0,
+                speed_step: 35.0,
+                max_speed: 650.0,
+                speed_up_interval: 30.0,
+                paddle_width: 1.0,
+                lives: 3,
+                capsule_every: 8,
+            },
+            Difficulty::Hard => DifficultyProfile {
+                start_speed: 430.0,
+                speed_step: 45.0,
+                max_speed: 800.0,
+                speed_up_interval: 20.0,
+                paddle_width: 0.8,
+                lives: 2,
+                capsule_every: 12,
+            },
+        }
+    }
+}
+
+/// Everything the chosen [`Difficulty`] changes about a game.
+#[derive(Clone, Copy, Debug)]
+pub struct DifficultyProfile {
+    /// Ball speed at launch, in pixels per second.
+    pub start_speed: f32,
+    /// Speed added each time the ball speeds up.
+    pub speed_step: f32,
+    pub max_speed: f32,
+    /// Seconds of play between time-based speed-ups.
+    pub speed_up_interval: f32,
+    /// Paddle width relative to the normal paddle.
+    pub paddle_width: f32,
+    pub lives: u32,
+    /// Under modern rules, every this many bricks destroyed the last one drops a paddle mode
+    /// capsule.
+    pub capsule_every: u32,
+}
+
+impl FromStr for Difficulty {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        })
+    }
+}
+
+/// Which game is played: the LearnOpenGL rules, or the 1976 arcade original with its fixed wall,
+/// shrinking paddle and points by row colour.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rules {
+    Modern,
+    Classic,
+}
+
+impl Rules {
+    pub const ALL: [Rules; 2] = [Rules::Modern, Rules::Classic];
+}
+
+impl FromStr for Rules {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "modern" => Ok(Rules::Modern),
+            "classic" => Ok(Rules::Classic),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Rules::Modern => "modern",
+            Rules::Classic => "classic",
+        })
+    }
+}
+
+/// Everything the player can configure, persisted to [`SETTINGS_PATH`] as an ini file that is
+/// meant to be edited by hand as well.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub window: WindowConfig,
+    pub integer_scaling: bool,
+    /// Pixels per second in game coordinates.
+    pub paddle_speed: f32,
+    /// Whether the paddle follows the mouse.
+    pub mouse_control: bool,
+    /// Scales mouse motion while the cursor is grabbed; an ungrabbed cursor moves the paddle to
+    /// wherever it points.
+    pub mouse_sensitivity: f32,
+    /// Confine and hide the cursor while playing.
+    pub grab_cursor: bool,
+    /// Stick deflection (0.0 - 0.9) ignored around the centre, so worn sticks don't drift.
+    pub stick_deadzone: f32,
+    pub difficulty: Difficulty,
+    pub rules: Rules,
+    /// Flattest angle above the horizontal the ball leaves the paddle at, in degrees.
+    pub min_bounce_angle: f32,
+    /// Steepest angle, 90 allows a bounce straight up.
+    pub max_bounce_angle: f32,
+    /// Let spin from a moving paddle bend the ball's flight.
+    pub curve_ball: bool,
+    /// 0.0 (muted) to 1.0.
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub bindings: InputBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window: WindowConfig::default(),
+            integer_scaling: false,
+            paddle_speed: 500.0,
+            mouse_control: true,
+            mouse_sensitivity: 1.0,
+            grab_cursor: false,
+            stick_deadzone: 0.2,
+            difficulty: Difficulty::Normal,
+            rules: Rules::Modern,
+            min_bounce_angle: 25.0,
+            max_bounce_angle: 80.0,
+            curve_ball: false,
+            music_volume: 0.8,
+            sfx_volume: 1.0,
+            bindings: InputBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Reads `path`, falling back to defaults for a missing file, missing keys or invalid values.
+    pub fn load(path: &str) -> Self {
+        let mut ini = IniFile::load(path);
+
+        let version = ini.get_or("meta", "version", 0u32);
+        if version > SETTINGS_VERSION {
+            println!(
+                "{} was written by a newer version ({} > {}), unknown keys are ignored",
+                path, version, SETTINGS_VERSION
+            );
+        }
+        migrate(&mut ini, version);
+
+        let default = Self::default();
+        let mut settings = Self {
+            window: WindowConfig::from_ini(&ini),
+            integer_scaling: ini.get_or("video", "integer_scaling", default.integer_scaling),
+            paddle_speed: ini.get_or("controls", "paddle_speed", default.paddle_speed),
+            mouse_control: ini.get_or("controls", "mouse_control", default.mouse_control),
+            mouse_sensitivity: ini.get_or(
+                "controls",
+                "mouse_sensitivity",
+                default.mouse_sensitivity,
+            ),
+            grab_cursor: ini.get_or("controls", "grab_cursor", default.grab_cursor),
+            stick_deadzone: ini.get_or("controls", "stick_deadzone", default.stick_deadzone),
+            difficulty: ini.get_or("game", "difficulty", default.difficulty),
+            rules: ini.get_or("game", "rules", default.rules),
+            min_bounce_angle: ini.get_or("physics", "min_bounce_angle", default.min_bounce_angle),
+            max_bounce_angle: ini.get_or("physics", "max_bounce_angle", default.max_bounce_angle),
+            curve_ball: ini.get_or("physics", "curve_ball", default.curve_ball),
+            music_volume: ini.get_or("audio", "music_volume", default.music_volume),
+            sfx_volume: ini.get_or("audio", "sfx_volume", default.sfx_volume),
+            bindings: InputBindings::from_ini(&ini),
+        };
+        settings.validate();
+        settings
+    }
+
+    /// Clamps every value into its supported range, warning about anything that was changed.
+    pub fn validate(&mut self) {
+        fn clamp(name: &str, value: &mut f32, min: f32, max: f32) {
+            let clamped = if value.is_nan() {
+                min
+            } else {
+                value.clamp(min, max)
+            };
+            if clamped != *value {
+                println!("{} = {} is out of range, using {}", name, value, clamped);
+                *value = clamped;
+            }
+        }
+
+        clamp(
+            "paddle_speed",
+            &mut self.paddle_speed,
+            MIN_PADDLE_SPEED,
+            MAX_PADDLE_SPEED,
+        );
+        clamp(
+            "mouse_sensitivity",
+            &mut self.mouse_sensitivity,
+            MIN_MOUSE_SENSITIVITY,
+            MAX_MOUSE_SENSITIVITY,
+        );
+        clamp(
+            "stick_deadzone",
+            &mut self.stick_deadzone,
+            0.0,
+            MAX_STICK_DEADZONE,
+        );
+        clamp(
+            "min_bounce_angle",
+            &mut self.min_bounce_angle,
+            MIN_BOUNCE_ANGLE,
+            MAX_BOUNCE_ANGLE,
+        );
+        clamp(
+            "max_bounce_angle",
+            &mut self.max_bounce_angle,
+            self.min_bounce_angle,
+            MAX_BOUNCE_ANGLE,
+        );
+        clamp("music_volume", &mut self.music_volume, 0.0, 1.0);
+        clamp("sfx_volume", &mut self.sfx_volume, 0.0, 1.0);
+
+        if self.window.width < 320 || self.window.height < 240 {
+            println!(
+                "window size {}x{} is too small, using 320x240",
+                self.window.width, self.window.height
+            );
+            self.window.width = self.window.width.max(320);
+            self.window.height = self.window.height.max(240);
+        }
+
+        if !MSAA_SAMPLES.contains(&self.window.msaa_samples) {
+            let samples = MSAA_SAMPLES
+                .into_iter()
+                .filter(|&samples| samples <= self.window.msaa_samples)
+                .max()
+                .unwrap_or(0);
+            println!(
+                "msaa = {} is not supported, using {}",
+                self.window.msaa_samples, samples
+            );
+            self.window.msaa_samples = samples;
+        }
+    }
+
+    pub fn save(&self, path: &str) {
+        let mut ini = IniFile::default();
+        ini.set("meta", "version", SETTINGS_VERSION);
+        self.window.to_ini(&mut ini);
+        ini.set("video", "integer_scaling", self.integer_scaling);
+        ini.set("controls", "paddle_speed", self.paddle_speed);
+        ini.set("controls", "mouse_control", self.mouse_control);
+        ini.set("controls", "mouse_sensitivity", self.mouse_sensitivity);
+        ini.set("controls", "grab_cursor", self.grab_cursor);
+        ini.set("controls", "stick_deadzone", self.stick_deadzone);
+        ini.set("game", "difficulty", self.difficulty);
+        ini.set("game", "rules", self.rules);
+        ini.set("physics", "min_bounce_angle", self.min_bounce_angle);
+        ini.set("physics", "max_bounce_angle", self.max_bounce_angle);
+        ini.set("physics", "curve_ball", self.curve_ball);
+        ini.set("audio", "music_volume", self.music_volume);
+        ini.set("audio", "sfx_volume", self.sfx_volume);
+        self.bindings.to_ini(&mut ini);
+
+        let contents = format!(
+            "# Breakout settings, safe to edit while the game is closed.\n\
+             # window mode: windowed | borderless | fullscreen, vsync: off | on | half\n\
+             # difficulty: easy | normal | hard, rules: modern | classic, volumes: 0.0 - 1.0\n\
+             # bindings: comma separated winit key names or gamepad buttons, e.g. KeyA, ArrowLeft,\n\
+             #   GamepadSouth, GamepadDPadLeft\n\n{}",
+            ini
+        );
+        if let Err(err) = std::fs::write(path, contents) {
+            println!("Failed to save settings to {}: {}", path, err);
+        }
+    }
+}
+
+// upgrades the keys of a file written by an older version in place
+fn migrate(ini: &mut IniFile, version: u32) {
+    if version < 1 {
+        // files from before the settings module only had a [window] section, whose keys are
+        // still read as they are
+        ini.set("meta", "version", 1);
+    }
+    if version < 2 {
+        // version 2 added gamepads; give saved bindings the default buttons next to their keys
+        for action in Action::ALL {
+            let Some(value) = ini.get("bindings", &action.to_string()) else {
+                continue;
+            };
+            let mut names = value.to_string();
+            for input in action.default_inputs() {
+                if let Input::Button(_) = input {
+                    names = format!("{}, {}", names, input.name());
+                }
+            }
+            ini.set(
+                "bindings",
+                &action.to_string(),
+                names.trim_start_matches(", "),
+            );
+        }
+        ini.set("meta", "version", 2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gamepad::GamepadButton;
+
+    #[test]
+    fn migrate_adds_gamepad_buttons_to_saved_bindings() {
+        let mut ini = IniFile::parse("[bindings]\nLaunch = KeyW\nPlayer2Left = KeyJ\n");
+        migrate(&mut ini, 1);
+        assert_eq!(ini.get("meta", "version"), Some("2"));
+        assert_eq!(ini.get("bindings", "Launch"), Some("KeyW, GamepadSouth"));
+        // actions without a default button keep just their keys
+        assert_eq!(ini.get("bindings", "Player2Left"), Some("KeyJ"));
+        // and actions that weren't saved keep their defaults
+        assert_eq!(ini.get("bindings", "Pause"), None);
+
+        let bindings = InputBindings::from_ini(&ini);
+        assert_eq!(
+            bindings.inputs(Action::Launch),
+            [
+                Input::Key(winit::keyboard::KeyCode::KeyW),
+                Input::Button(GamepadButton::South)
+            ]
+        );
+    }
+
+    #[test]
+    fn migrate_leaves_current_files_alone() {
+        let mut ini = IniFile::parse("[meta]\nversion = 2\n[bindings]\nLaunch = KeyW\n");
+        migrate(&mut ini, SETTINGS_VERSION);
+        assert_eq!(ini.get("bindings", "Launch"), Some("KeyW"));
+    }
+
+    #[test]
+    fn validate_clamps_out_of_range_values() {
+        let mut settings = Settings {
+            paddle_speed: 10.0,
+            mouse_sensitivity: f32::NAN,
+            stick_deadzone: 2.0,
+            min_bounce_angle: 60.0,
+            max_bounce_angle: 30.0,
+            music_volume: -1.0,
+            sfx_volume: 3.0,
+            ..Settings::default()
+        };
+        settings.window.width = 100;
+        settings.window.msaa_samples = 6;
+        settings.validate();
+
+        assert_eq!(settings.paddle_speed, MIN_PADDLE_SPEED);
+        assert_eq!(settings.mouse_sensitivity, MIN_MOUSE_SENSITIVITY);
+        assert_eq!(settings.stick_deadzone, MAX_STICK_DEADZONE);
+        // the largest angle can't go below the smallest
+        assert_eq!(settings.min_bounce_angle, 60.0);
+        assert_eq!(settings.max_bounce_angle, 60.0);
+        assert_eq!(settings.music_volume, 0.0);
+        assert_eq!(settings.sfx_volume, 1.0);
+        assert_eq!(settings.window.width, 320);
+        assert_eq!(settings.window.msaa_samples, 4);
+    }
+
+    #[test]
+    fn validate_keeps_defaults() {
+        let mut settings = Settings::default();
+        settings.validate();
+        let default = Settings::default();
+        assert_eq!(settings.paddle_speed, default.paddle_speed);
+        assert_eq!(settings.min_bounce_angle, default.min_bounce_angle);
+        assert_eq!(settings.max_bounce_angle, default.max_bounce_angle);
+        assert_eq!(settings.window.msaa_samples, default.window.msaa_samples);
+    }
+}
@@ -1,7 +1,6 @@
 use std::rc::Rc;
 
 use glow::*;
-use nalgebra_glm as glm;
 
 #[derive(Clone)]
 pub struct Shader {
@@ -94,15 +93,6 @@ impl Shader {
         }
     }
 
-    pub fn set_vector3f(&self, name: &str, value: &glm::TVec3<f32>) {
-        //let name_c_string = CString::new(name).unwrap();
-        unsafe {
-            let location = self.gl.get_uniform_location(self.program, name).unwrap();
-            self.gl
-                .uniform_3_f32(Some(&location), value.x, value.y, value.z);
-        }
-    }
-
     pub fn clean(&self) {
         unsafe {
             self.gl.delete_program(self.program);
@@ -0,0 +1,198 @@
+use std::{
+    fmt,
+    io::{
+        self,
+        ErrorKind,
+        Write,
+    },
+    net::{
+        Ipv4Addr,
+        TcpListener,
+        TcpStream,
+    },
+};
+
+use nalgebra_glm as glm;
+
+// snapshots a watcher may fall behind by, in bytes, before it is dropped as too slow
+const MAX_PENDING: usize = 1 << 20;
+
+/// The game as a watcher sees it after one update. Each one is sent as a line of JSON:
+///
+/// ```text
+/// {"tick":812,"state":"active","mode":"standard","score":120,"lives":2,
+///  "paddles":[{"x":350.0,"y":580.0,"w":100.0,"h":20.0}],
+///  "balls":[{"x":392.5,"y":301.2,"vx":-180.0,"vy":-310.4,"r":12.5,"stuck":false}],
+///  "bricks":[[0.0,0.0,53.3,25.0,0,7],...]}
+/// ```
+///
+/// all on one line. Bricks still standing are `[x, y, width, height, solid, points]`, with
+/// `solid` 1 for bricks the ball can't break. Versus and co-op add each player's score as
+/// `"scores":[player one, player two]`, and list player two's paddle after player one's.
+pub struct Snapshot {
+    pub state: &'static str,
+    pub mode: &'static str,
+    pub score: u32,
+    pub lives: u32,
+    pub player_scores: Option<[u32; 2]>,
+    /// Position and size of each paddle.
+    pub paddles: Vec<(glm::TVec2<f32>, glm::TVec2<f32>)>,
+    pub balls: Vec<BallSnapshot>,
+    pub bricks: Vec<BrickSnapshot>,
+}
+
+pub struct BallSnapshot {
+    pub position: glm::TVec2<f32>,
+    pub velocity: glm::TVec2<f32>,
+    pub radius: f32,
+    pub stuck: bool,
+}
+
+pub struct BrickSnapshot {
+    pub position: glm::TVec2<f32>,
+    pub size: glm::TVec2<f32>,
+    pub solid: bool,
+    pub points: u32,
+}
+
+// the snapshot as JSON, numbered with the tick it was taken on
+struct Line<'a>(u64, &'a Snapshot);
+
+impl fmt::Display for Line<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Line(tick, snapshot) = self;
+        // the names are our own, none of them need escaping
+        write!(
+            f,
+            r#"{{"tick":{},"state":"{}","mode":"{}","score":{},"lives":{}"#,
+            tick, snapshot.state, snapshot.mode, snapshot.score, snapshot.lives
+        )?;
+        if let Some([one, two]) = snapshot.player_scores {
+            write!(f, r#","scores":[{},{}]"#, one, two)?;
+        }
+        f.write_str(r#","paddles":["#)?;
+        for (i, (position, size)) in snapshot.paddles.iter().enumerate() {
+            write!(
+                f,
+                r#"{}{{"x":{:.1},"y":{:.1},"w":{:.1},"h":{:.1}}}"#,
+                separator(i),
+                position.x,
+                position.y,
+                size.x,
+                size.y
+            )?;
+        }
+        f.write_str(r#"],"balls":["#)?;
+        for (i, ball) in snapshot.balls.iter().enumerate() {
+            write!(
+                f,
+                r#"{}{{"x":{:.1},"y":{:.1},"vx":{:.1},"vy":{:.1},"r":{:.1},"stuck":{}}}"#,
+                separator(i),
+                ball.position.x,
+                ball.position.y,
+                ball.velocity.x,
+                ball.velocity.y,
+                ball.radius,
+                ball.stuck
+            )?;
+        }
+        f.write_str(r#"],"bricks":["#)?;
+        for (i, brick) in snapshot.bricks.iter().enumerate() {
+            write!(
+                f,
+                "{}[{:.1},{:.1},{:.1},{:.1},{},{}]",
+                separator(i),
+                brick.position.x,
+                brick.position.y,
+                brick.size.x,
+                brick.size.y,
+                brick.solid as u8,
+                brick.points
+            )?;
+        }
+        f.write_str("]}")
+    }
+}
+
+fn separator(i: usize) -> &'static str {
+    if i == 0 { "" } else { "," }
+}
+
+// one connected watcher and what it hasn't been sent yet
+struct Watcher {
+    stream: TcpStream,
+    pending: Vec<u8>,
+}
+
+impl Watcher {
+    // sends what the socket takes without waiting, false once the watcher is gone or too far
+    // behind to keep
+    fn flush(&mut self) -> bool {
+        while !self.pending.is_empty() {
+            match self.stream.write(&self.pending) {
+                Ok(0) => return false,
+                Ok(written) => {
+                    self.pending.drain(..written);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => return false,
+            }
+        }
+        self.pending.len() <= MAX_PENDING
+    }
+}
+
+/// Streams a [`Snapshot`] of the game after every update to whoever connects to a local TCP
+/// port, so tools can follow the game without linking against it. Nothing is read from the
+/// watchers, and the game never waits on them.
+pub struct SpectatorServer {
+    listener: TcpListener,
+    watchers: Vec<Watcher>,
+    tick: u64,
+}
+
+impl SpectatorServer {
+    /// Listens on `port` of this machine only.
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            watchers: Vec::new(),
+            tick: 0,
+        })
+    }
+
+    /// Counts an update and sends the `snapshot` of it to every watcher, letting in whoever has
+    /// connected since the last one first. With nobody watching no snapshot is taken.
+    pub fn update(&mut self, snapshot: impl FnOnce() -> Snapshot) {
+        self.accept();
+        self.tick += 1;
+        if self.watchers.is_empty() {
+            return;
+        }
+        let line = format!("{}\n", Line(self.tick, &snapshot()));
+        self.watchers.retain_mut(|watcher| {
+            watcher.pending.extend(line.as_bytes());
+            let keep = watcher.flush();
+            if !keep {
+                println!("Spectator disconnected");
+            }
+            keep
+        });
+    }
+
+    fn accept(&mut self) {
+        while let Ok((stream, address)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_err() {
+                continue;
+            }
+            let _ = stream.set_nodelay(true);
+            println!("Spectator connected from {}", address);
+            self.watchers.push(Watcher {
+                stream,
+                pending: Vec::new(),
+            });
+        }
+    }
+}
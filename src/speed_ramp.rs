@@ -0,0 +1,64 @@
+use crate::settings::DifficultyProfile;
+
+// bricks destroyed at which the ball speeds up, as in the arcade original
+const BRICK_MILESTONES: [u32; 2] = [4, 12];
+
+/// Tracks what makes the ball faster over a level: the 4th and 12th brick, first reaching the
+/// top rows, and every `speed_up_interval` seconds of play. How much each step adds comes from
+/// the [`DifficultyProfile`].
+#[derive(Clone)]
+pub struct SpeedRamp {
+    steps: u32,
+    bricks_destroyed: u32,
+    top_rows_reached: bool,
+    // play time since the last time-based step
+    elapsed: f32,
+}
+
+impl SpeedRamp {
+    pub fn new() -> Self {
+        Self {
+            steps: 0,
+            bricks_destroyed: 0,
+            top_rows_reached: false,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Counts a destroyed brick. Returns whether the ball should speed up.
+    pub fn brick_destroyed(&mut self, in_top_rows: bool) -> bool {
+        self.bricks_destroyed += 1;
+        let mut sped_up = false;
+        if BRICK_MILESTONES.contains(&self.bricks_destroyed) {
+            self.steps += 1;
+            sped_up = true;
+        }
+        if in_top_rows && !self.top_rows_reached {
+            self.top_rows_reached = true;
+            self.steps += 1;
+            sped_up = true;
+        }
+        sped_up
+    }
+
+    /// Advances the play clock while the ball is in flight. Returns whether the ball should
+    /// speed up.
+    pub fn update(&mut self, dt: f32, profile: &DifficultyProfile) -> bool {
+        self.elapsed += dt;
+        if self.elapsed < profile.speed_up_interval {
+            return false;
+        }
+        self.elapsed -= profile.speed_up_interval;
+        self.steps += 1;
+        true
+    }
+
+    /// Ball speed in pixels per second.
+    pub fn speed(&self, profile: &DifficultyProfile) -> f32 {
+        (profile.start_speed + profile.speed_step * self.steps as f32).min(profile.max_speed)
+    }
+}
@@ -1,4 +1,10 @@
-use std::rc::Rc;
+use std::{
+    cell::{
+        Cell,
+        RefCell,
+    },
+    rc::Rc,
+};
 
 use glow::{
     Context,
@@ -11,10 +17,71 @@ use crate::{
     texture::Texture2D,
 };
 
+// maximum number of quads uploaded to the GPU in one go; bigger batches are split
+const MAX_QUADS: usize = 1000;
+// <vec2 position, vec2 texCoords, vec4 color>
+const FLOATS_PER_VERTEX: usize = 8;
+const VERTICES_PER_QUAD: usize = 4;
+const INDICES_PER_QUAD: usize = 6;
+
+struct Quad {
+    layer: u32,
+    texture: Texture2D,
+    vertices: [f32; FLOATS_PER_VERTEX * VERTICES_PER_QUAD],
+}
+
+/// How to place a sprite. Start from [`SpriteDesc::new`] and override what you need.
+#[derive(Clone, Copy, Debug)]
+pub struct SpriteDesc {
+    pub position: glm::TVec2<f32>,
+    pub size: glm::TVec2<f32>,
+    /// Degrees, clockwise on screen.
+    pub rotation: f32,
+    /// Point to rotate around, relative to the sprite: (0, 0) is the top-left corner and (1, 1)
+    /// the bottom-right.
+    pub pivot: glm::TVec2<f32>,
+    /// Part of the texture to sample, relative to the texture (or atlas region) it is drawn with:
+    /// (u_min, v_min, u_max, v_max).
+    pub uv_rect: [f32; 4],
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub color: glm::TVec4<f32>,
+}
+
+impl SpriteDesc {
+    pub fn new(position: glm::TVec2<f32>, size: glm::TVec2<f32>) -> Self {
+        Self {
+            position,
+            size,
+            rotation: 0.0,
+            pivot: glm::vec2(0.5, 0.5),
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+            flip_x: false,
+            flip_y: false,
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    pub vertices: u32,
+    pub sprites: u32,
+}
+
+/// Collects sprites into a dynamic vertex buffer and draws them with as few draw calls as
+/// possible. Sprites are grouped by layer first (lower layers are drawn first) and by texture
+/// within a layer, so a draw call is only issued when the texture changes.
 pub struct SpriteRenderer {
     shader: Shader,
     gl: Rc<Context>,
     quad_vao: Option<NativeVertexArray>,
+    vbo: Option<NativeBuffer>,
+    ebo: Option<NativeBuffer>,
+    batch: RefCell<Vec<Quad>>,
+    layer: Cell<u32>,
+    stats: Cell<RenderStats>,
 }
 
 impl SpriteRenderer {
@@ -23,6 +90,11 @@ impl SpriteRenderer {
             shader,
             gl,
             quad_vao: None,
+            vbo: None,
+            ebo: None,
+            batch: RefCell::new(Vec::with_capacity(MAX_QUADS)),
+            layer: Cell::new(0),
+            stats: Cell::new(RenderStats::default()),
         };
 
         result.init_rendering_data();
@@ -31,21 +103,23 @@ impl SpriteRenderer {
     }
 
     fn init_rendering_data(&mut self) {
-        #[rustfmt::skip]
-        let vertices = [
-            // pos      // tex
-            0.0f32, 1.0, 0.0, 1.0,
-            1.0, 0.0, 1.0, 0.0,
-            0.0, 0.0, 0.0, 0.0,
-
-            0.0, 1.0, 0.0, 1.0,
-            1.0, 1.0, 1.0, 1.0,
-            1.0, 0.0, 1.0, 0.0
-        ];
+        // every quad shares the same index pattern, only offset by its first vertex
+        let indices = (0..MAX_QUADS as u32)
+            .flat_map(|quad| {
+                let base = quad * VERTICES_PER_QUAD as u32;
+                [base, base + 1, base + 2, base + 2, base + 3, base]
+            })
+            .collect::<Vec<u32>>();
+
+        let stride = (FLOATS_PER_VERTEX * std::mem::size_of::<f32>()) as i32;
 
         unsafe {
-            self.gl.polygon_mode(glow::FRONT_AND_BACK, glow::FILL);
-            let vbo = self.gl.create_buffer().expect("Cannot create buffer");
+            // not available (and always filled) on GLES
+            if !self.gl.version().is_embedded {
+                self.gl.polygon_mode(glow::FRONT_AND_BACK, glow::FILL);
+            }
+            self.vbo = Some(self.gl.create_buffer().expect("Cannot create buffer"));
+            self.ebo = Some(self.gl.create_buffer().expect("Cannot create buffer"));
             self.quad_vao = Some(
                 self.gl
                     .create_vertex_array()
@@ -53,30 +127,53 @@ impl SpriteRenderer {
             );
 
             self.gl.bind_vertex_array(self.quad_vao);
-            self.gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, self.vbo);
+            self.gl.buffer_data_size(
+                glow::ARRAY_BUFFER,
+                (MAX_QUADS * VERTICES_PER_QUAD) as i32 * stride,
+                glow::DYNAMIC_DRAW,
+            );
 
+            self.gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, self.ebo);
             self.gl.buffer_data_u8_slice(
-                glow::ARRAY_BUFFER,
-                bytemuck::cast_slice(&vertices[..]),
+                glow::ELEMENT_ARRAY_BUFFER,
+                bytemuck::cast_slice(&indices[..]),
                 glow::STATIC_DRAW,
             );
 
+            // <vec2 position, vec2 texCoords>
+            self.gl
+                .vertex_attrib_pointer_f32(0, 4, glow::FLOAT, false, stride, 0);
+            self.gl.enable_vertex_attrib_array(0);
+            // <vec4 color>
             self.gl.vertex_attrib_pointer_f32(
-                0,
+                1,
                 4,
                 glow::FLOAT,
                 false,
+                stride,
                 4 * std::mem::size_of::<f32>() as i32,
-                0,
             );
+            self.gl.enable_vertex_attrib_array(1);
 
-            self.gl.enable_vertex_attrib_array(0);
-
-            self.gl.bind_buffer(glow::ARRAY_BUFFER, None);
             self.gl.bind_vertex_array(None);
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, None);
+            self.gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, None);
         }
     }
 
+    /// Starts a new frame: drops anything still queued and resets the counters.
+    pub fn begin(&self) {
+        self.batch.borrow_mut().clear();
+        self.layer.set(0);
+        self.stats.set(RenderStats::default());
+    }
+
+    /// Sprites queued after this call are drawn on top of sprites queued on lower layers.
+    pub fn set_layer(&self, layer: u32) {
+        self.layer.set(layer);
+    }
+
     pub fn draw_sprite(
         &self,
         texture: &Texture2D,
@@ -84,28 +181,127 @@ impl SpriteRenderer {
         size: &glm::TVec2<f32>,
         color: &glm::TVec3<f32>,
     ) {
-        self.shader.use_program();
+        self.draw(
+            texture,
+            &SpriteDesc {
+                color: glm::vec4(color.x, color.y, color.z, 1.0),
+                ..SpriteDesc::new(*position, *size)
+            },
+        );
+    }
+
+    pub fn draw(&self, texture: &Texture2D, desc: &SpriteDesc) {
+        // corners in clockwise order starting top-left, rotated around the pivot
+        let pivot = desc.position + desc.size.component_mul(&desc.pivot);
+        let (sin, cos) = desc.rotation.to_radians().sin_cos();
+        let corners = [
+            glm::vec2(0.0, 0.0),
+            glm::vec2(1.0, 0.0),
+            glm::vec2(1.0, 1.0),
+            glm::vec2(0.0, 1.0),
+        ]
+        .map(|corner| {
+            let offset = desc.position + desc.size.component_mul(&corner) - pivot;
+            pivot
+                + glm::vec2(
+                    offset.x * cos - offset.y * sin,
+                    offset.x * sin + offset.y * cos,
+                )
+        });
+
+        // the descriptor's uv rect is relative to the texture's own (atlas) rect
+        let [region_u0, region_v0, region_u1, region_v1] = texture.uv_rect();
+        let lerp_u = |t: f32| region_u0 + (region_u1 - region_u0) * t;
+        let lerp_v = |t: f32| region_v0 + (region_v1 - region_v0) * t;
+        let (mut u0, mut u1) = (lerp_u(desc.uv_rect[0]), lerp_u(desc.uv_rect[2]));
+        let (mut v0, mut v1) = (lerp_v(desc.uv_rect[1]), lerp_v(desc.uv_rect[3]));
+        if desc.flip_x {
+            std::mem::swap(&mut u0, &mut u1);
+        }
+        if desc.flip_y {
+            std::mem::swap(&mut v0, &mut v1);
+        }
+
+        let [top_left, top_right, bottom_right, bottom_left] = corners;
+        let (r, g, b, a) = (desc.color.x, desc.color.y, desc.color.z, desc.color.w);
 
-        let mut model = glm::Mat4::identity();
+        #[rustfmt::skip]
+        let vertices = [
+            // pos                              // tex  // color
+            top_left.x,     top_left.y,         u0, v0, r, g, b, a,
+            top_right.x,    top_right.y,        u1, v0, r, g, b, a,
+            bottom_right.x, bottom_right.y,     u1, v1, r, g, b, a,
+            bottom_left.x,  bottom_left.y,      u0, v1, r, g, b, a,
+        ];
 
-        model = glm::translate(&model, &glm::vec3(position.x, position.y, 0.0));
-        model = glm::translate(&model, &glm::vec3(0.5 * size.x, 0.5 * size.y, 0.0));
-        model = glm::rotate(&model, 0.0f32.to_radians(), &glm::vec3(0.0, 0.0, 1.0));
-        model = glm::translate(&model, &glm::vec3(-0.5 * size.x, -0.5 * size.y, 0.0));
-        model = glm::scale(&model, &glm::vec3(size.x, size.y, 1.0));
+        self.batch.borrow_mut().push(Quad {
+            layer: self.layer.get(),
+            texture: texture.clone(),
+            vertices,
+        });
+    }
 
-        self.shader.matrix_4_f32("model", model.as_slice());
+    /// Draws everything queued since the last flush, one draw call per texture change.
+    pub fn flush(&self) {
+        let mut batch = self.batch.borrow_mut();
+        if batch.is_empty() {
+            return;
+        }
+
+        // stable, so sprites sharing a layer and texture keep their submission order
+        batch.sort_by_key(|quad| (quad.layer, quad.texture.id()));
 
-        self.shader.set_vector3f("spriteColor", &color);
+        let mut stats = self.stats.get();
+        stats.sprites += batch.len() as u32;
+
+        self.shader.use_program();
 
         unsafe {
             self.gl.active_texture(glow::TEXTURE0);
-            texture.bind();
-
             self.gl.bind_vertex_array(self.quad_vao);
-            self.gl.draw_arrays(glow::TRIANGLES, 0, 6);
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, self.vbo);
+        }
+
+        let mut vertices = Vec::with_capacity(MAX_QUADS * FLOATS_PER_VERTEX * VERTICES_PER_QUAD);
+        for run in batch.chunk_by(|a, b| a.texture.id() == b.texture.id()) {
+            run[0].texture.bind();
+            for chunk in run.chunks(MAX_QUADS) {
+                vertices.clear();
+                for quad in chunk {
+                    vertices.extend_from_slice(&quad.vertices);
+                }
+
+                unsafe {
+                    self.gl.buffer_sub_data_u8_slice(
+                        glow::ARRAY_BUFFER,
+                        0,
+                        bytemuck::cast_slice(&vertices[..]),
+                    );
+                    self.gl.draw_elements(
+                        glow::TRIANGLES,
+                        (chunk.len() * INDICES_PER_QUAD) as i32,
+                        glow::UNSIGNED_INT,
+                        0,
+                    );
+                }
+
+                stats.draw_calls += 1;
+                stats.vertices += (chunk.len() * VERTICES_PER_QUAD) as u32;
+            }
+        }
+
+        unsafe {
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, None);
             self.gl.bind_vertex_array(None);
         }
+
+        batch.clear();
+        self.stats.set(stats);
+    }
+
+    /// Counters accumulated since the last call to [`SpriteRenderer::begin`].
+    pub fn stats(&self) -> RenderStats {
+        self.stats.get()
     }
 }
 
@@ -114,6 +310,8 @@ impl Drop for SpriteRenderer {
     fn drop(&mut self) {
         unsafe {
             self.gl.delete_vertex_array(self.quad_vao.unwrap());
+            self.gl.delete_buffer(self.vbo.unwrap());
+            self.gl.delete_buffer(self.ebo.unwrap());
         }
     }
 }
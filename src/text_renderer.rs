@@ -0,0 +1,180 @@
+use std::{
+    collections::HashMap,
+    rc::Rc,
+};
+
+use ab_glyph::{
+    Font,
+    FontVec,
+    PxScale,
+    ScaleFont,
+};
+use glow::Context;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use nalgebra_glm as glm;
+
+use crate::{
+    sprite_renderer::{
+        SpriteDesc,
+        SpriteRenderer,
+    },
+    texture::Texture2D,
+    texture_atlas::AtlasBuilder,
+};
+
+const GLYPH_PAGE_SIZE: u32 = 1024;
+
+struct Character {
+    texture: Rc<Texture2D>,
+    size: glm::TVec2<f32>,
+    // offset from the pen position on the baseline to the glyph's top-left corner
+    bearing: glm::TVec2<f32>,
+    advance: f32,
+}
+
+/// Renders ASCII text with glyphs rasterized once from a TrueType font into a texture page,
+/// drawn as sprites through the [`SpriteRenderer`] so text batches like everything else.
+pub struct TextRenderer {
+    gl: Rc<Context>,
+    characters: HashMap<char, Character>,
+    // distance from the top of a line to its baseline at scale 1.0
+    ascent: f32,
+    line_height: f32,
+}
+
+impl TextRenderer {
+    pub fn new(gl: Rc<Context>) -> Self {
+        Self {
+            gl,
+            characters: HashMap::new(),
+            ascent: 0.0,
+            line_height: 0.0,
+        }
+    }
+
+    /// Rasterizes the printable ASCII characters of `font` at `font_size` pixels.
+    pub fn load(&mut self, font: &str, font_size: f32) {
+        let data = std::fs::read(font).expect("Failed to read font");
+        let font = FontVec::try_from_vec(data).expect("Failed to parse font");
+        let scaled = font.as_scaled(PxScale::from(font_size));
+
+        self.ascent = scaled.ascent();
+        self.line_height = scaled.height() + scaled.line_gap();
+
+        let mut builder = AtlasBuilder::new(GLYPH_PAGE_SIZE, 1);
+        let mut metrics = Vec::new();
+        for c in (32u8..127).map(char::from) {
+            let glyph = scaled.scaled_glyph(c);
+            let advance = scaled.h_advance(glyph.id);
+            let outline = scaled.outline_glyph(glyph);
+
+            let (image, bearing) = match outline {
+                Some(outline) => {
+                    let bounds = outline.px_bounds();
+                    let mut image = RgbaImage::new(
+                        bounds.width().ceil().max(1.0) as u32,
+                        bounds.height().ceil().max(1.0) as u32,
+                    );
+                    outline.draw(|x, y, coverage| {
+                        if x < image.width() && y < image.height() {
+                            let alpha = (coverage.clamp(0.0, 1.0) * 255.0) as u8;
+                            image.put_pixel(x, y, Rgba([255, 255, 255, alpha]));
+                        }
+                    });
+                    (image, glm::vec2(bounds.min.x, bounds.min.y))
+                }
+                // whitespace has nothing to draw but still advances the pen
+                None => (RgbaImage::new(1, 1), glm::vec2(0.0, 0.0)),
+            };
+
+            metrics.push((c, image.dimensions(), bearing, advance));
+            builder.add_image(&c.to_string(), image);
+        }
+
+        let atlas = builder.build();
+        let pages = atlas
+            .pages
+            .iter()
+            .map(|page| {
+                let texture = Texture2D::new(self.gl.clone());
+                texture.generate(page.width(), page.height(), page.as_raw());
+                texture
+            })
+            .collect::<Vec<_>>();
+
+        self.characters.clear();
+        for (c, (width, height), bearing, advance) in metrics {
+            let name = c.to_string();
+            let region = atlas.regions[&name];
+            self.characters.insert(
+                c,
+                Character {
+                    texture: Rc::new(pages[region.page].region(atlas.uv_rect(&name).unwrap())),
+                    size: glm::vec2(width as f32, height as f32),
+                    bearing,
+                    advance,
+                },
+            );
+        }
+    }
+
+    /// Width of `text` in pixels when drawn at `scale`.
+    pub fn measure(&self, text: &str, scale: f32) -> f32 {
+        text.chars()
+            .filter_map(|c| self.characters.get(&c))
+            .map(|character| character.advance * scale)
+            .sum()
+    }
+
+    pub fn line_height(&self, scale: f32) -> f32 {
+        self.line_height * scale
+    }
+
+    /// Draws `text` with the top-left corner of its first line at `position`.
+    pub fn draw_text(
+        &self,
+        renderer: &SpriteRenderer,
+        text: &str,
+        position: glm::TVec2<f32>,
+        scale: f32,
+        color: glm::TVec4<f32>,
+    ) {
+        let mut pen = glm::vec2(position.x, position.y + self.ascent * scale);
+        for c in text.chars() {
+            if c == '\n' {
+                pen = glm::vec2(position.x, pen.y + self.line_height * scale);
+                continue;
+            }
+            let Some(character) = self.characters.get(&c) else {
+                continue;
+            };
+            if c != ' ' {
+                renderer.draw(
+                    &character.texture,
+                    &SpriteDesc {
+                        color,
+                        ..SpriteDesc::new(pen + character.bearing * scale, character.size * scale)
+                    },
+                );
+            }
+            pen.x += character.advance * scale;
+        }
+    }
+
+    /// Draws `text` horizontally centred on `center_x`.
+    pub fn draw_text_centered(
+        &self,
+        renderer: &SpriteRenderer,
+        text: &str,
+        center_x: f32,
+        y: f32,
+        scale: f32,
+        color: glm::TVec4<f32>,
+    ) {
+        let x = center_x - self.measure(text, scale) / 2.0;
+        self.draw_text(renderer, text, glm::vec2(x, y), scale, color);
+    }
+}
@@ -6,6 +6,8 @@ use glow::*;
 pub struct Texture2D {
     gl: Rc<Context>,
     texture: NativeTexture,
+    // part of the GL texture this handle refers to: (u_min, v_min, u_max, v_max)
+    uv_rect: [f32; 4],
 }
 
 impl Texture2D {
@@ -15,7 +17,25 @@ impl Texture2D {
             texture = gl.create_texture().unwrap();
         }
 
-        Self { gl, texture }
+        Self {
+            gl,
+            texture,
+            uv_rect: [0.0, 0.0, 1.0, 1.0],
+        }
+    }
+
+    /// A handle to a sub-rectangle of this texture, as handed out for sprites packed into an
+    /// atlas page. It shares the GL texture, so sprites from the same page batch together.
+    pub fn region(&self, uv_rect: [f32; 4]) -> Self {
+        Self {
+            gl: self.gl.clone(),
+            texture: self.texture,
+            uv_rect,
+        }
+    }
+
+    pub fn uv_rect(&self) -> [f32; 4] {
+        self.uv_rect
     }
 
     pub fn generate(&self, width: u32, height: u32, data: &[u8]) {
@@ -51,6 +71,10 @@ impl Texture2D {
         }
     }
 
+    pub fn id(&self) -> NativeTexture {
+        self.texture
+    }
+
     pub fn bind(&self) {
         unsafe {
             self.gl.bind_texture(glow::TEXTURE_2D, Some(self.texture));
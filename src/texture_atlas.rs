@@ -0,0 +1,251 @@
+use std::{
+    collections::HashMap,
+    fs,
+};
+
+use image::RgbaImage;
+
+/// Where a packed image ended up: the page it lives on and its pixel rectangle on that page.
+#[derive(Clone, Copy, Debug)]
+pub struct AtlasRegion {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub struct TextureAtlas {
+    pub pages: Vec<RgbaImage>,
+    pub regions: HashMap<String, AtlasRegion>,
+}
+
+/// Packs images into square pages using shelves: images are sorted by height and laid out left to
+/// right, starting a new shelf when a row is full and a new page when a page is full.
+pub struct AtlasBuilder {
+    page_size: u32,
+    padding: u32,
+    images: Vec<(String, RgbaImage)>,
+}
+
+impl AtlasBuilder {
+    pub fn new(page_size: u32, padding: u32) -> Self {
+        Self {
+            page_size,
+            padding,
+            images: Vec::new(),
+        }
+    }
+
+    pub fn add_image(&mut self, name: &str, image: RgbaImage) {
+        self.images.push((name.to_string(), image));
+    }
+
+    /// Adds an image computed from one that was already added, e.g. a sprite sheet generated from
+    /// a single sprite. Does nothing if `source` is unknown.
+    pub fn derive_image(
+        &mut self,
+        source: &str,
+        name: &str,
+        derive: impl FnOnce(&RgbaImage) -> RgbaImage,
+    ) {
+        let derived = self
+            .images
+            .iter()
+            .find(|(image_name, _)| image_name == source)
+            .map(|(_, image)| derive(image));
+        if let Some(image) = derived {
+            self.add_image(name, image);
+        }
+    }
+
+    /// Adds every `.png` in `dir`, named after the file stem (`block_solid.png` ->
+    /// `block_solid`).
+    pub fn add_dir(&mut self, dir: &str) {
+        let mut paths = fs::read_dir(dir)
+            .expect("Failed to read texture directory")
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "png"))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            let name = path.file_stem().unwrap().to_string_lossy().to_string();
+            println!("Packing texture from file: {}", path.display());
+            let image = image::open(&path)
+                .expect("Failed to load texture")
+                .to_rgba8();
+            self.add_image(&name, image);
+        }
+    }
+
+    pub fn build(mut self) -> TextureAtlas {
+        // tallest first keeps the shelves tight
+        self.images.sort_by(|(a_name, a), (b_name, b)| {
+            b.height().cmp(&a.height()).then(a_name.cmp(b_name))
+        });
+
+        let mut atlas = TextureAtlas {
+            pages: Vec::new(),
+            regions: HashMap::new(),
+        };
+
+        let padding = self.padding;
+        let (mut cursor_x, mut cursor_y, mut shelf_height) = (0, 0, 0);
+
+        for (name, image) in self.images {
+            let padded_width = image.width() + padding * 2;
+            let padded_height = image.height() + padding * 2;
+
+            // images too big for a shared page get one of their own
+            if padded_width > self.page_size || padded_height > self.page_size {
+                let mut page = RgbaImage::new(padded_width, padded_height);
+                blit_extruded(&mut page, &image, 0, 0, padding);
+                atlas.pages.push(page);
+                atlas.regions.insert(
+                    name,
+                    AtlasRegion {
+                        page: atlas.pages.len() - 1,
+                        x: padding,
+                        y: padding,
+                        width: image.width(),
+                        height: image.height(),
+                    },
+                );
+                // the shared page being filled is no longer the last one
+                cursor_y = self.page_size;
+                continue;
+            }
+
+            if cursor_x + padded_width > self.page_size {
+                cursor_x = 0;
+                cursor_y += shelf_height;
+                shelf_height = 0;
+            }
+            if atlas.pages.is_empty() || cursor_y + padded_height > self.page_size {
+                atlas
+                    .pages
+                    .push(RgbaImage::new(self.page_size, self.page_size));
+                cursor_x = 0;
+                cursor_y = 0;
+                shelf_height = 0;
+            }
+
+            let page_index = atlas.pages.len() - 1;
+            blit_extruded(
+                &mut atlas.pages[page_index],
+                &image,
+                cursor_x,
+                cursor_y,
+                padding,
+            );
+            atlas.regions.insert(
+                name,
+                AtlasRegion {
+                    page: page_index,
+                    x: cursor_x + padding,
+                    y: cursor_y + padding,
+                    width: image.width(),
+                    height: image.height(),
+                },
+            );
+
+            cursor_x += padded_width;
+            shelf_height = shelf_height.max(padded_height);
+        }
+
+        atlas
+    }
+}
+
+// copies `image` into `page` at (x + padding, y + padding) and repeats its border pixels into the
+// padding, so linear filtering at the region edges never samples a neighbouring image
+fn blit_extruded(page: &mut RgbaImage, image: &RgbaImage, x: u32, y: u32, padding: u32) {
+    let (width, height) = image.dimensions();
+    for py in 0..height + padding * 2 {
+        for px in 0..width + padding * 2 {
+            let src_x = px.saturating_sub(padding).min(width - 1);
+            let src_y = py.saturating_sub(padding).min(height - 1);
+            page.put_pixel(x + px, y + py, *image.get_pixel(src_x, src_y));
+        }
+    }
+}
+
+impl TextureAtlas {
+    /// Region of `name` in normalized texture coordinates: `(u_min, v_min, u_max, v_max)`.
+    pub fn uv_rect(&self, name: &str) -> Option<[f32; 4]> {
+        let region = self.regions.get(name)?;
+        let page = self.pages.get(region.page)?;
+        let (page_width, page_height) = page.dimensions();
+        Some([
+            region.x as f32 / page_width as f32,
+            region.y as f32 / page_height as f32,
+            (region.x + region.width) as f32 / page_width as f32,
+            (region.y + region.height) as f32 / page_height as f32,
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    fn image(width: u32, height: u32, shade: u8) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([shade, shade, shade, 255]))
+    }
+
+    #[test]
+    fn packs_shelves_tallest_first() {
+        let mut builder = AtlasBuilder::new(64, 1);
+        builder.add_image("small", image(10, 10, 1));
+        builder.add_image("tall", image(20, 30, 2));
+        builder.add_image("wide", image(40, 20, 3));
+        builder.add_image("next", image(20, 20, 4));
+        let atlas = builder.build();
+
+        assert_eq!(atlas.pages.len(), 1);
+        let region = |name: &str| {
+            let region = atlas.regions[name];
+            (region.page, region.x, region.y, region.width, region.height)
+        };
+        // the first shelf is as tall as its tallest image, padding included
+        assert_eq!(region("tall"), (0, 1, 1, 20, 30));
+        assert_eq!(region("next"), (0, 23, 1, 20, 20));
+        // too wide for what is left of the first shelf
+        assert_eq!(region("wide"), (0, 1, 33, 40, 20));
+        assert_eq!(region("small"), (0, 43, 33, 10, 10));
+    }
+
+    #[test]
+    fn starts_new_pages_and_gives_big_images_their_own() {
+        let mut builder = AtlasBuilder::new(32, 0);
+        builder.add_image("a", image(32, 20, 1));
+        builder.add_image("b", image(32, 20, 2));
+        builder.add_image("huge", image(40, 10, 3));
+        let atlas = builder.build();
+
+        assert_eq!(atlas.pages.len(), 3);
+        assert_eq!(atlas.regions["a"].page, 0);
+        assert_eq!(atlas.regions["b"].page, 1);
+        assert_eq!(atlas.regions["huge"].page, 2);
+        assert_eq!(atlas.pages[2].dimensions(), (40, 10));
+    }
+
+    #[test]
+    fn extrudes_borders_into_the_padding() {
+        let mut builder = AtlasBuilder::new(16, 2);
+        let mut sprite = image(2, 2, 0);
+        sprite.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        builder.add_image("sprite", sprite);
+        let atlas = builder.build();
+
+        let page = &atlas.pages[0];
+        assert_eq!(*page.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*page.get_pixel(2, 2), Rgba([255, 0, 0, 255]));
+        assert_eq!(*page.get_pixel(5, 5), Rgba([0, 0, 0, 255]));
+        let region = atlas.regions["sprite"];
+        assert_eq!((region.x, region.y), (2, 2));
+    }
+}
@@ -0,0 +1,82 @@
+/// A finished level of a time attack run.
+pub struct Split {
+    pub level: String,
+    pub time: f32,
+    /// The personal best for the level before this run, if there was one.
+    pub best: Option<f32>,
+}
+
+impl Split {
+    /// Seconds over (positive) or under the personal best.
+    pub fn delta(&self) -> Option<f32> {
+        self.best.map(|best| self.time - best)
+    }
+}
+
+/// A timed run through the campaign. Each level's clock starts when the ball is first launched
+/// and stops when the level is completed; losing the ball doesn't stop it.
+pub struct TimeAttack {
+    pub splits: Vec<Split>,
+    /// Seconds on the current level's clock.
+    pub level_time: f32,
+    running: bool,
+}
+
+impl TimeAttack {
+    pub fn new() -> Self {
+        Self {
+            splits: Vec::new(),
+            level_time: 0.0,
+            running: false,
+        }
+    }
+
+    /// Starts the clock, if it isn't running already.
+    pub fn launch(&mut self) {
+        self.running = true;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.running {
+            self.level_time += dt;
+        }
+    }
+
+    /// Puts the current level's clock back to zero, to run again from the next launch.
+    pub fn restart_level(&mut self) {
+        self.level_time = 0.0;
+        self.running = false;
+    }
+
+    /// Records the current level as finished and gets the clock ready for the next one.
+    pub fn finish_level(&mut self, level: &str, best: Option<f32>) {
+        self.splits.push(Split {
+            level: level.to_string(),
+            time: self.level_time,
+            best,
+        });
+        self.restart_level();
+    }
+
+    /// Seconds over every finished level.
+    pub fn total(&self) -> f32 {
+        self.splits.iter().map(|split| split.time).sum()
+    }
+}
+
+/// `m:ss.cc`
+pub fn format_time(seconds: f32) -> String {
+    let centiseconds = (seconds.max(0.0) * 100.0).round() as u32;
+    format!(
+        "{}:{:02}.{:02}",
+        centiseconds / 6000,
+        centiseconds / 100 % 60,
+        centiseconds % 100
+    )
+}
+
+/// `+s.cc` or `-s.cc`
+pub fn format_delta(delta: f32) -> String {
+    let sign = if delta < 0.0 { '-' } else { '+' };
+    format!("{}{:.2}", sign, delta.abs())
+}
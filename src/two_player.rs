@@ -0,0 +1,93 @@
+use crate::level_generator::{
+    Coloring,
+    GeneratorConfig,
+    Symmetry,
+    generate,
+};
+
+/// Player one, on the bottom paddle.
+pub const PLAYER_ONE: usize = 0;
+/// Player two, on the top paddle in versus and beside player one in co-op.
+pub const PLAYER_TWO: usize = 1;
+
+/// Who did what in a game for two players. In versus, between the bottom and the top paddle,
+/// each player loses a life when the ball gets past their own edge of the field; in co-op the
+/// players share the game's lives and only their scores are kept apart.
+#[derive(Clone)]
+pub struct TwoPlayer {
+    /// Each player's own lives, in versus.
+    pub lives: [u32; 2],
+    pub scores: [u32; 2],
+    /// The player whose paddle holds the ball until they serve it.
+    pub server: usize,
+    /// The player the ball last bounced off, who scores the bricks it breaks.
+    pub last_hit: usize,
+}
+
+impl TwoPlayer {
+    pub fn new(lives: u32) -> Self {
+        Self {
+            lives: [lives; 2],
+            scores: [0; 2],
+            server: PLAYER_ONE,
+            last_hit: PLAYER_ONE,
+        }
+    }
+
+    /// Takes a life from `player`, who serves next.
+    pub fn lose_life(&mut self, player: usize) {
+        self.lives[player] = self.lives[player].saturating_sub(1);
+        self.server = player;
+        self.last_hit = player;
+    }
+
+    /// Hands the next serve to the other player, in co-op.
+    pub fn pass_serve(&mut self) {
+        self.server = 1 - self.server;
+        self.last_hit = self.server;
+    }
+
+    /// Whether a player is out of lives.
+    pub fn is_over(&self) -> bool {
+        self.lives.contains(&0)
+    }
+
+    /// The player left with lives, or once the bricks are gone, the higher score. None for a draw.
+    pub fn winner(&self) -> Option<usize> {
+        if self.lives[PLAYER_ONE] == 0 {
+            return Some(PLAYER_TWO);
+        }
+        if self.lives[PLAYER_TWO] == 0 {
+            return Some(PLAYER_ONE);
+        }
+        match self.scores[PLAYER_ONE].cmp(&self.scores[PLAYER_TWO]) {
+            std::cmp::Ordering::Greater => Some(PLAYER_ONE),
+            std::cmp::Ordering::Less => Some(PLAYER_TWO),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+pub fn player_name(player: usize) -> &'static str {
+    match player {
+        PLAYER_ONE => "Player one",
+        _ => "Player two",
+    }
+}
+
+/// A field of bricks for the middle of the screen: a generated half facing the top paddle, a gap,
+/// and the same half upside down facing the bottom one, so neither side has the better of it.
+pub fn field_tiles(seed: u64) -> Vec<Vec<u32>> {
+    let config = GeneratorConfig {
+        rows: 3,
+        density: 0.75,
+        symmetry: Symmetry::Mirror,
+        coloring: Coloring::Bands(1),
+        ..GeneratorConfig::default()
+    };
+    let half = generate(&config, seed);
+    let mut tiles = half.clone();
+    tiles.push(vec![0; config.columns]);
+    tiles.extend(half.into_iter().rev());
+    tiles
+}
@@ -0,0 +1,104 @@
+use glow::{
+    Context,
+    HasContext,
+};
+use nalgebra_glm as glm;
+
+/// Maps the fixed virtual resolution the game is simulated and drawn in onto whatever the window
+/// currently measures in physical pixels, keeping the aspect ratio and filling the rest with bars
+/// (letterbox above/below, pillarbox left/right).
+pub struct Viewport {
+    pub virtual_width: u32,
+    pub virtual_height: u32,
+    // only scale by whole multiples so pixels stay crisp; leaves wider bars
+    integer_scaling: bool,
+    physical_width: u32,
+    physical_height: u32,
+    // drawable rectangle in physical pixels, origin at the window's top-left corner
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Viewport {
+    pub fn new(virtual_width: u32, virtual_height: u32) -> Self {
+        let mut viewport = Self {
+            virtual_width,
+            virtual_height,
+            integer_scaling: false,
+            physical_width: virtual_width,
+            physical_height: virtual_height,
+            x: 0,
+            y: 0,
+            width: virtual_width,
+            height: virtual_height,
+        };
+        viewport.resize(virtual_width, virtual_height);
+        viewport
+    }
+
+    /// Recomputes the drawable rectangle for a window of the given size in physical pixels.
+    pub fn resize(&mut self, physical_width: u32, physical_height: u32) {
+        self.physical_width = physical_width.max(1);
+        self.physical_height = physical_height.max(1);
+
+        let scale_x = self.physical_width as f32 / self.virtual_width as f32;
+        let scale_y = self.physical_height as f32 / self.virtual_height as f32;
+        let mut scale = scale_x.min(scale_y);
+        if self.integer_scaling && scale >= 1.0 {
+            scale = scale.floor();
+        }
+
+        self.width = ((self.virtual_width as f32 * scale).round() as u32).max(1);
+        self.height = ((self.virtual_height as f32 * scale).round() as u32).max(1);
+        self.x = self.physical_width.saturating_sub(self.width) / 2;
+        self.y = self.physical_height.saturating_sub(self.height) / 2;
+    }
+
+    pub fn integer_scaling(&self) -> bool {
+        self.integer_scaling
+    }
+
+    pub fn set_integer_scaling(&mut self, integer_scaling: bool) {
+        self.integer_scaling = integer_scaling;
+        self.resize(self.physical_width, self.physical_height);
+    }
+
+    /// Clears the whole window (bars included) and restricts drawing to the game area.
+    pub fn apply(&self, gl: &Context) {
+        unsafe {
+            gl.viewport(
+                0,
+                0,
+                self.physical_width as i32,
+                self.physical_height as i32,
+            );
+            gl.clear_color(0.0, 0.0, 0.0, 1.0);
+            gl.clear(glow::COLOR_BUFFER_BIT);
+
+            // GL counts rows from the bottom of the window
+            let gl_y = self.physical_height - self.y - self.height;
+            gl.viewport(
+                self.x as i32,
+                gl_y as i32,
+                self.width as i32,
+                self.height as i32,
+            );
+        }
+    }
+
+    /// How far one physical pixel of mouse motion moves in virtual game coordinates.
+    pub fn world_units_per_pixel(&self) -> f32 {
+        self.virtual_width as f32 / self.width as f32
+    }
+
+    /// Converts a position in physical window pixels (as reported by `CursorMoved`) to virtual
+    /// game coordinates. Positions over the bars map outside `0..virtual_width/height`.
+    pub fn screen_to_world(&self, x: f64, y: f64) -> glm::TVec2<f32> {
+        glm::vec2(
+            (x as f32 - self.x as f32) * self.virtual_width as f32 / self.width as f32,
+            (y as f32 - self.y as f32) * self.virtual_height as f32 / self.height as f32,
+        )
+    }
+}
@@ -1,18 +1,23 @@
 use std::{
+    fmt,
     num::NonZeroU32,
     rc::Rc,
+    str::FromStr,
 };
 
 use glow::Context;
 use glutin::{
     config::{
+        Config,
         ConfigTemplateBuilder,
         GlConfig,
     },
     context::{
         ContextApi,
         ContextAttributesBuilder,
+        NotCurrentContext,
         PossiblyCurrentContext,
+        Version,
     },
     display::GetGlDisplay,
     prelude::{
@@ -30,7 +35,181 @@ use glutin_winit::{
     DisplayBuilder,
     GlWindow,
 };
-use winit::event_loop::EventLoop;
+use raw_window_handle::RawWindowHandle;
+use winit::{
+    event_loop::EventLoop,
+    monitor::MonitorHandle,
+    window::Fullscreen,
+};
+
+use crate::ini::IniFile;
+
+// tried in order until the driver gives us a context; the shaders are rewritten to match
+const GL_VERSIONS: [ContextApi; 4] = [
+    ContextApi::OpenGl(Some(Version { major: 4, minor: 1 })),
+    ContextApi::OpenGl(Some(Version { major: 3, minor: 3 })),
+    ContextApi::Gles(Some(Version { major: 3, minor: 0 })),
+    ContextApi::Gles(None),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowMode {
+    Windowed,
+    /// Covers the monitor with a borderless window at the desktop resolution.
+    Borderless,
+    /// Switches the monitor to the video mode closest to the configured size.
+    Fullscreen,
+}
+
+impl FromStr for WindowMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "windowed" => Ok(WindowMode::Windowed),
+            "borderless" => Ok(WindowMode::Borderless),
+            "fullscreen" => Ok(WindowMode::Fullscreen),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for WindowMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WindowMode::Windowed => "windowed",
+            WindowMode::Borderless => "borderless",
+            WindowMode::Fullscreen => "fullscreen",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VsyncMode {
+    Off,
+    On,
+    /// Present every other vertical blank, e.g. 30 fps on a 60 Hz monitor.
+    Half,
+}
+
+impl FromStr for VsyncMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" | "false" => Ok(VsyncMode::Off),
+            "on" | "true" => Ok(VsyncMode::On),
+            "half" => Ok(VsyncMode::Half),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for VsyncMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VsyncMode::Off => "off",
+            VsyncMode::On => "on",
+            VsyncMode::Half => "half",
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WindowConfig {
+    pub title: String,
+    /// Logical size of the window in windowed mode, and the preferred video mode in fullscreen.
+    pub width: u32,
+    pub height: u32,
+    pub mode: WindowMode,
+    /// Index into the list of connected monitors, the primary monitor when `None`.
+    pub monitor: Option<usize>,
+    pub vsync: VsyncMode,
+    /// 0 disables multisampling.
+    pub msaa_samples: u8,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "Breakout".to_string(),
+            width: 1024,
+            height: 768,
+            mode: WindowMode::Windowed,
+            monitor: None,
+            vsync: VsyncMode::On,
+            msaa_samples: 4,
+        }
+    }
+}
+
+impl WindowConfig {
+    /// Reads the `[window]` section of a settings file, keeping defaults for missing keys.
+    pub fn from_ini(ini: &IniFile) -> Self {
+        let default = Self::default();
+        Self {
+            title: ini
+                .get("window", "title")
+                .map(str::to_string)
+                .unwrap_or(default.title),
+            width: ini.get_or("window", "width", default.width).max(1),
+            height: ini.get_or("window", "height", default.height).max(1),
+            mode: ini.get_or("window", "mode", default.mode),
+            monitor: ini
+                .get("window", "monitor")
+                .and_then(|monitor| monitor.parse().ok()),
+            vsync: ini.get_or("window", "vsync", default.vsync),
+            msaa_samples: ini.get_or("window", "msaa", default.msaa_samples),
+        }
+    }
+
+    pub fn to_ini(&self, ini: &mut IniFile) {
+        ini.set("window", "title", &self.title);
+        ini.set("window", "width", self.width);
+        ini.set("window", "height", self.height);
+        ini.set("window", "mode", self.mode);
+        if let Some(monitor) = self.monitor {
+            ini.set("window", "monitor", monitor);
+        }
+        ini.set("window", "vsync", self.vsync);
+        ini.set("window", "msaa", self.msaa_samples);
+    }
+}
+
+/// Builds the `Fullscreen` value winit expects for `mode` on `monitor`; `None` means windowed.
+pub fn fullscreen_for(
+    mode: WindowMode,
+    monitor: Option<MonitorHandle>,
+    width: u32,
+    height: u32,
+) -> Option<Fullscreen> {
+    match mode {
+        WindowMode::Windowed => None,
+        WindowMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+        WindowMode::Fullscreen => {
+            // closest resolution to the requested one, then the highest refresh rate
+            let video_mode = monitor?.video_modes().min_by_key(|video_mode| {
+                let size = video_mode.size();
+                (
+                    size.width.abs_diff(width) + size.height.abs_diff(height),
+                    u32::MAX - video_mode.refresh_rate_millihertz(),
+                )
+            });
+            match video_mode {
+                Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+                None => Some(Fullscreen::Borderless(None)),
+            }
+        }
+    }
+}
+
+pub fn swap_interval_for(vsync: VsyncMode) -> SwapInterval {
+    match vsync {
+        VsyncMode::Off => SwapInterval::DontWait,
+        VsyncMode::On => SwapInterval::Wait(NonZeroU32::new(1).unwrap()),
+        VsyncMode::Half => SwapInterval::Wait(NonZeroU32::new(2).unwrap()),
+    }
+}
 
 pub struct Window {
     pub gl: Rc<Context>,
@@ -41,24 +220,50 @@ pub struct Window {
 }
 
 impl Window {
-    pub fn build() -> Self {
+    pub fn build(config: &WindowConfig) -> Self {
         use raw_window_handle::HasRawWindowHandle;
 
         let event_loop = winit::event_loop::EventLoopBuilder::new().build().unwrap();
-        let window_builder = winit::window::WindowBuilder::new()
-            .with_title("Hello triangle!")
-            .with_inner_size(winit::dpi::LogicalSize::new(1024.0, 768.0));
 
-        let template = ConfigTemplateBuilder::new();
+        let monitor = config
+            .monitor
+            .and_then(|index| event_loop.available_monitors().nth(index))
+            .or_else(|| event_loop.primary_monitor());
+
+        let mut window_builder = winit::window::WindowBuilder::new()
+            .with_title(config.title.as_str())
+            .with_inner_size(winit::dpi::LogicalSize::new(
+                config.width as f64,
+                config.height as f64,
+            ))
+            .with_fullscreen(fullscreen_for(
+                config.mode,
+                monitor.clone(),
+                config.width,
+                config.height,
+            ));
+        if let (WindowMode::Windowed, Some(monitor)) = (config.mode, &monitor) {
+            window_builder = window_builder.with_position(monitor.position());
+        }
+
+        let mut template = ConfigTemplateBuilder::new();
+        if config.msaa_samples > 0 {
+            template = template.with_multisampling(config.msaa_samples);
+        }
 
         let display_builder = DisplayBuilder::new().with_window_builder(Some(window_builder));
 
+        let requested_samples = config.msaa_samples;
         let (window, gl_config) = display_builder
             .build(&event_loop, template, |configs| {
-                use glutin::config::Config;
+                // the most samples not above what was asked for
                 configs
                     .reduce(|accum: Config, config: Config| {
-                        if config.num_samples() > accum.num_samples() {
+                        let fits = config.num_samples() <= requested_samples;
+                        let accum_fits = accum.num_samples() <= requested_samples;
+                        if (fits && !accum_fits)
+                            || (fits == accum_fits && config.num_samples() > accum.num_samples())
+                        {
                             config
                         } else {
                             accum
@@ -69,41 +274,31 @@ impl Window {
             .unwrap();
 
         let raw_window_handle = window.as_ref().map(|window| window.raw_window_handle());
-
-        let gl_display = gl_config.display();
-        let context_attributes = ContextAttributesBuilder::new()
-            .with_context_api(ContextApi::OpenGl(Some(glutin::context::Version {
-                major: 4,
-                minor: 1,
-            })))
-            .build(raw_window_handle);
-
-        let not_current_gl_context = unsafe {
-            gl_display
-                .create_context(&gl_config, &context_attributes)
-                .unwrap()
-        };
+        let not_current_gl_context = create_context(&gl_config, raw_window_handle);
 
         let window = window.unwrap();
 
         let attrs = window.build_surface_attributes(Default::default());
         let gl_surface = unsafe {
-            gl_display
+            gl_config
+                .display()
                 .create_window_surface(&gl_config, &attrs)
                 .unwrap()
         };
 
         let gl_context = not_current_gl_context.make_current(&gl_surface).unwrap();
 
+        let gl_display = gl_config.display();
         let gl = unsafe {
             Rc::new(glow::Context::from_loader_function_cstr(|s| {
                 gl_display.get_proc_address(s)
             }))
         };
 
-        gl_surface
-            .set_swap_interval(&gl_context, SwapInterval::Wait(NonZeroU32::new(1).unwrap()))
-            .unwrap();
+        if let Err(err) = gl_surface.set_swap_interval(&gl_context, swap_interval_for(config.vsync))
+        {
+            println!("Could not set vsync to {:?}: {}", config.vsync, err);
+        }
 
         Self {
             gl,
@@ -114,3 +309,23 @@ impl Window {
         }
     }
 }
+
+fn create_context(
+    gl_config: &Config,
+    raw_window_handle: Option<RawWindowHandle>,
+) -> NotCurrentContext {
+    let gl_display = gl_config.display();
+    for api in GL_VERSIONS {
+        let context_attributes = ContextAttributesBuilder::new()
+            .with_context_api(api)
+            .build(raw_window_handle);
+        match unsafe { gl_display.create_context(gl_config, &context_attributes) } {
+            Ok(context) => {
+                println!("Created {:?} context", api);
+                return context;
+            }
+            Err(err) => println!("Could not create {:?} context: {}", api, err),
+        }
+    }
+    panic!("No supported OpenGL or OpenGL ES version available");
+}